@@ -23,6 +23,11 @@ mkdir -p ${PREFIX}/lib
 cp target/release/fuzzer ${PREFIX}
 cp target/release/*.a ${PREFIX}/lib
 cp target/release/log_reader ${PREFIX}
+cp target/release/cmin ${PREFIX}
+cp target/release/tmin ${PREFIX}
+cp target/release/parmesan-showmap ${PREFIX}
+cp target/release/parmesan-inspect ${PREFIX}
+cp target/release/parmesan-sync-server ${PREFIX}
 
 cd llvm_mode
 rm -rf build
@@ -31,6 +36,7 @@ cd build
 cmake -DCMAKE_INSTALL_PREFIX=${PREFIX} -DCMAKE_BUILD_TYPE=Release ..
 make # VERBOSE=1 
 make install # VERBOSE=1
+(cd ${ROOT_DIR}/tests && BIN_DIR=${PREFIX} ./check_libfuzzer.sh)
 
 #llvm-diff-parmesan
 (cd ${ROOT_DIR}/tools/llvm-diff-parmesan && mkdir -p build && cd build && cmake .. && cmake --build . && cp llvm-diff-parmesan ../../../bin/)
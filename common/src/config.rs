@@ -10,6 +10,8 @@ pub const PREFER_FAST_COND: bool = true;
 
 // ************ Resources ****************
 pub const MAX_INPUT_LEN: usize = 15000;
+// shm_input.rs
+pub const INPUT_SHM_SIZE: usize = 1 << 20;
 
 // branch.rs
 pub const MAP_SIZE_POW2: usize = 20;
@@ -17,9 +19,15 @@ pub const BRANCHES_SIZE: usize = 1 << MAP_SIZE_POW2;
 
 // executor.rs:
 pub const TMOUT_SKIP: usize = 3;
-pub const TIME_LIMIT: u64 = 1;
+pub const TIME_LIMIT: u64 = 1000; // ms
 pub const MEM_LIMIT: u64 = 200; // MB
-pub const TIME_LIMIT_TRACK: u64 = 12;
+pub const TIME_LIMIT_TRACK: u64 = 12000; // ms, at least
+pub const TIME_LIMIT_TRACK_FACTOR: u64 = 12;
+pub const TIME_LIMIT_SANOPT_FACTOR: u64 = 3;
+// -T auto: the dry run uses the max, then the limit is set to a multiple of the slowest seed.
+pub const AUTO_TIME_LIMIT_FACTOR: u64 = 5;
+pub const AUTO_TIME_LIMIT_MIN: u64 = 20; // ms
+pub const AUTO_TIME_LIMIT_MAX: u64 = 10000; // ms
 pub const MEM_LIMIT_TRACK: u64 = 0;
 pub const LONG_FUZZ_TIME: usize = 8;
 pub const MAX_INVARIABLE_NUM: usize = 16;
@@ -27,6 +35,16 @@ pub const MAX_NUM_MINIMAL_OPTIMA_ALL: usize = 28;
 // based the bit bucket: [1], [2], [3], [4, 7], [8, 15], [16, 31], [32, 127], [128, infinity]
 pub const MAX_COND_ORDER: u32 = 16;
 
+// triage
+pub const CRASH_STACK_DEPTH: usize = 3;
+pub const CRASH_VERIFY_RUNS: usize = 3;
+
+// fuzz_main.rs
+pub const SAVE_STATE_INTERVAL: u64 = 300; // seconds
+
+// cfg.rs: the most cmps whose score is updated per batch of edges, the rest wait for the next batch.
+pub const CFG_PROPAGATE_LIMIT: usize = 4096;
+
 // ************ Mutation ****************
 // SEARCH
 pub const ENABLE_DET_MUTATION: bool = true;
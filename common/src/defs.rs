@@ -7,6 +7,7 @@ pub static ANGORA_BIN_DIR: &str = "ANGORA_BIN_DIR";
 pub static TRACK_OUTPUT_VAR: &str = "ANGORA_TRACK_OUTPUT";
 pub static COND_STMT_ENV_VAR: &str = "ANGORA_COND_STMT_SHM_ID";
 pub static BRANCHES_SHM_ENV_VAR: &str = "ANGORA_BRANCHES_SHM_ID";
+pub static INPUT_SHM_ENV_VAR: &str = "ANGORA_INPUT_SHM_ID";
 pub static LD_LIBRARY_PATH_VAR: &str = "LD_LIBRARY_PATH";
 pub static ASAN_OPTIONS_VAR: &str = "ASAN_OPTIONS";
 pub static MSAN_OPTIONS_VAR: &str = "MSAN_OPTIONS";
@@ -15,21 +16,55 @@ pub static ASAN_OPTIONS_CONTENT: &str =
 pub const MSAN_ERROR_CODE: i32 = 86;
 pub static MSAN_OPTIONS_CONTENT: &str =
     "exit_code=86:symbolize=0:abort_on_error=1:allocator_may_return_null=1:msan_track_origins=0";
+// Used when reproducing a crash for its report.
+pub static ASAN_OPTIONS_REPORT_CONTENT: &str =
+    "abort_on_error=1:detect_leaks=0:symbolize=1:allocator_may_return_null=1";
+pub static MSAN_OPTIONS_REPORT_CONTENT: &str =
+    "exit_code=86:symbolize=1:abort_on_error=1:allocator_may_return_null=1:msan_track_origins=0";
+pub static CRASH_REPORT_EXT: &str = "report";
 
 // depot.rs
 pub static CRASHES_DIR: &str = "crashes";
+pub static FLAKY_DIR: &str = "flaky"; // in CRASHES_DIR
 pub static HANGS_DIR: &str = "hangs";
 pub static INPUTS_DIR: &str = "queue";
+// Inputs dropped in by hand while fuzzing.
+pub static INBOX_DIR: &str = "inbox";
 
 // forksrv.rs
 pub static ENABLE_FORKSRV: &str = "ANGORA_ENABLE_FORKSRV";
 pub static FORKSRV_SOCKET_PATH_VAR: &str = "ANGORA_FORKSRV_SOCKET_PATH";
+pub static TRACK_FORKSRV_SOCKET_PATH_VAR: &str = "ANGORA_TRACK_FORKSRV_SOCKET_PATH";
+// runtime_fast/src/persistent.rs, read in forkcli.rs
+pub static PERSIST_ENV_VAR: &str = "ANGORA_PERSISTENT";
 
 // command.rs
 pub static ANGORA_DIR_NAME: &str = "angora";
 pub static ANGORA_LOG_FILE: &str = "angora.csv";
 pub static COND_QUEUE_FILE: &str = "cond_queue.csv";
 pub static CHART_STAT_FILE: &str = "chart_stat.json";
+pub static SYNC_STATS_FILE: &str = "sync_stats.json";
+pub static TARGET_PROGRESS_JSON_FILE: &str = "target_progress.json";
+pub static TARGET_PROGRESS_CSV_FILE: &str = "target_progress.csv";
+
+// resume.rs
+pub static STATE_DIR: &str = "state";
+pub static QUEUE_STATE_FILE: &str = "cond_queue.json";
+pub static CFG_STATE_FILE: &str = "cfg.json";
+pub static TARGET_STATE_FILE: &str = "targets.json";
+// The time limit in ms, to keep the one of -T auto.
+pub static TIME_LIMIT_STATE_FILE: &str = "time_limit";
+// Written last, so a state dir without it is incomplete.
+pub static STATE_DONE_FILE: &str = "done";
+pub static VIRGIN_BRANCHES_FILE: &str = "virgin_branches";
+pub static TMOUTS_BRANCHES_FILE: &str = "tmouts_branches";
+pub static CRASHES_BRANCHES_FILE: &str = "crashes_branches";
+pub static LEARNED_CFG_FILE: &str = "learned_cfg.json";
+
+// dyncfg/export.rs
+pub static CFG_EXPORT_JSON_FILE: &str = "cfg_export.json";
+pub static CFG_EXPORT_DOT_FILE: &str = "cfg_export.dot";
+pub static CFG_EXPORT_GRAPHML_FILE: &str = "cfg_export.graphml";
 
 pub const SLOW_SPEED: u32 = 888888;
 pub const UNREACHABLE: u64 = std::u64::MAX;
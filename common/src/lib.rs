@@ -3,6 +3,7 @@ pub mod config;
 pub mod defs;
 pub mod log_data;
 pub mod shm;
+pub mod shm_input;
 pub mod tag;
 
 
@@ -0,0 +1,11 @@
+use crate::config::INPUT_SHM_SIZE;
+
+// The test case in shared memory, written by the fuzzer (fuzzer/src/executor/shm_input.rs)
+// and read by the harness via __angora_input_buf (runtime_fast/src/shm_input.rs).
+#[repr(C)] // It should be repr C since we will used it in shared memory
+pub struct ShmInputBuf {
+    pub len: u32,
+    // Set by the harness when it fetches the input, so the fuzzer can tell it is not ignored.
+    pub fetched: u32,
+    pub buf: [u8; INPUT_SHM_SIZE],
+}
@@ -0,0 +1,62 @@
+#[macro_use]
+extern crate clap;
+use clap::{App, Arg};
+
+extern crate angora;
+extern crate angora_common;
+use angora::cmin_main;
+
+fn main() {
+    let matches = App::new("angora-cmin")
+        .version(crate_version!())
+        .about("Minimize a corpus: keep the smallest set of inputs with the same branch coverage.")
+        .arg(Arg::with_name("input_dir")
+            .short("i")
+            .long("input")
+            .value_name("DIR")
+            .help("Sets the directory of inputs, e.g. the queue of a previous run")
+            .takes_value(true)
+            .required(true))
+        .arg(Arg::with_name("output_dir")
+            .short("o")
+            .long("output")
+            .value_name("DIR")
+            .help("Sets the directory for the minimized corpus, it should not exist or be empty")
+            .takes_value(true)
+            .required(true))
+        .arg(Arg::with_name("pargs")
+            .help("Targeted program (USE_FAST) and arguments. Any \"@@\" will be substituted with the input filename.")
+            .required(true)
+            .multiple(true)
+            .allow_hyphen_values(true)
+            .last(true)
+            .index(1))
+        .arg(Arg::with_name("memory_limit")
+            .short("M")
+            .long("memory_limit")
+            .value_name("MEM")
+            .help("Memory limit for programs, default is 200(MB), set 0 for unlimit memory")
+            .takes_value(true))
+        .arg(Arg::with_name("time_limit")
+            .short("T")
+            .long("time_limit")
+            .value_name("TIME")
+            .help("time limit for programs: N seconds or Nms, default is 1(s)")
+            .takes_value(true))
+        .arg(Arg::with_name("cfg_file")
+            .short("c")
+            .long("cfg")
+            .value_name("TARGET_FILE")
+            .help("Prefer the inputs that reach the targets in this file (JSON file)")
+            .takes_value(true))
+        .get_matches();
+
+    cmin_main(
+        matches.value_of("input_dir").unwrap(),
+        matches.value_of("output_dir").unwrap(),
+        matches.values_of_lossy("pargs").unwrap(),
+        value_t!(matches, "memory_limit", u64).unwrap_or(angora_common::config::MEM_LIMIT),
+        matches.value_of("time_limit"),
+        matches.value_of("cfg_file"),
+    );
+}
@@ -1,15 +1,16 @@
 #[macro_use]
 extern crate clap;
-use clap::{App, Arg};
+use clap::{App, AppSettings, Arg, SubCommand};
 
 extern crate angora;
 extern crate angora_common;
-use angora::fuzz_main;
+use angora::{fuzz_main, replay_main, SyncOpt};
 
 fn main() {
     let matches = App::new("angora-fuzzer")
         .version(crate_version!())
         .about("Angora is a mutation-based fuzzer. The main goal of Angora is to increase branch coverage by solving path constraints without symbolic execution.")
+        .setting(AppSettings::SubcommandsNegateReqs)
         .arg(Arg::with_name("mode")
             .short("m")
             .long("mode")
@@ -60,7 +61,7 @@ fn main() {
             .short("T")
             .long("time_limit")
             .value_name("TIME")
-            .help("time limit for programs, default is 1(s), the tracking timeout is 12 * TIME")
+            .help("time limit for programs: N seconds, Nms, or auto (calibrated from the seeds), default is 1(s), the tracking timeout is 12 * TIME")
             .takes_value(true))
         .arg(Arg::with_name("thread_jobs")
             .short("j")
@@ -74,10 +75,55 @@ fn main() {
             .value_name("SearchMethod")
             .help("Which search method to run the program in?")
             .possible_values(&["gd", "random", "mb"]))
+        .arg(Arg::with_name("distance")
+            .long("distance")
+            .value_name("STRATEGY")
+            .help("How the distance of a branch to the targets is aggregated from its successors: harmonic mean (default), min, coverage (sum), or weighted (harmonic mean weighted by how often each edge is seen)")
+            .possible_values(&["harmonic", "min", "coverage", "weighted"]))
         .arg(Arg::with_name("sync_afl")
             .short("S")
             .long("sync_afl")
             .help("Sync the seeds with AFL. Output directory should be in AFL's directory structure."))
+        .arg(Arg::with_name("sync_dir")
+            .long("sync_dir")
+            .value_name("DIR")
+            .help("Sync the seeds with a corpus directory of another fuzzer (e.g. libFuzzer, honggfuzz), the file names can be anything. Can be given several times.")
+            .takes_value(true)
+            .multiple(true)
+            .number_of_values(1))
+        .arg(Arg::with_name("main")
+            .long("main")
+            .value_name("NAME")
+            .help("Run as the main instance NAME, in the subdirectory NAME of the output directory shared with the other instances, and sync with them")
+            .takes_value(true)
+            .conflicts_with("secondary"))
+        .arg(Arg::with_name("secondary")
+            .long("secondary")
+            .value_name("NAME")
+            .help("Run as the secondary instance NAME, like --main but without the deterministic stage")
+            .takes_value(true))
+        .arg(Arg::with_name("sync_server")
+            .long("sync_server")
+            .value_name("ADDR")
+            .help("Sync the inputs and what the cfg learned with the other machines of the campaign through the sync server at ADDR (HOST:PORT), see parmesan-sync-server")
+            .takes_value(true))
+        .arg(Arg::with_name("import_cfg")
+            .long("import_cfg")
+            .value_name("FILE")
+            .help("Start with the cfg edges, indirect-call edges, magic bytes and solved targets learned by another campaign (its learned_cfg.json, or state/cfg.json). Can be given several times.")
+            .takes_value(true)
+            .multiple(true)
+            .number_of_values(1))
+        .arg(Arg::with_name("import_edges")
+            .long("import_edges")
+            .value_name("FILE")
+            .help("Add the edges of a cfg export (cfg_export.json of another run) to the edges of the cfg file. Can be given several times.")
+            .takes_value(true)
+            .multiple(true)
+            .number_of_values(1))
+        .arg(Arg::with_name("sync_conds")
+            .long("sync_conds")
+            .help("With --main or --secondary, import the condition queues of the other instances too"))
         .arg(Arg::with_name("disable_afl_mutation")
             .short("A")
             .long("disable_afl_mutation")
@@ -103,8 +149,73 @@ fn main() {
             .value_name("FUNC2")
             .help("Function info txt file")
             .takes_value(true))
+        .arg(Arg::with_name("shm_input")
+            .long("shm_input")
+            .help("Deliver inputs to the fast and sanopt programs via shared memory. The harness should read them with __angora_input_buf()."))
+        .subcommand(SubCommand::with_name("replay")
+            .about("Replay the queue and the crashes of a campaign on a new build, and report what changed")
+            .arg(Arg::with_name("input_dir")
+                .short("i")
+                .long("input")
+                .value_name("DIR")
+                .help("Sets the output directory of the old campaign")
+                .takes_value(true)
+                .required(true))
+            .arg(Arg::with_name("output_dir")
+                .short("o")
+                .long("output")
+                .value_name("DIR")
+                .help("Sets the directory of outputs, it can be fuzzed with \"-i -\" afterwards")
+                .takes_value(true)
+                .required(true))
+            .arg(Arg::with_name("track_target")
+                .short("t")
+                .long("track")
+                .value_name("PROM")
+                .help("Sets the new target (USE_TRACK) for tracking")
+                .takes_value(true)
+                .required(true))
+            .arg(Arg::with_name("cfg_file")
+                .short("c")
+                .long("cfg")
+                .value_name("TARGET_FILE")
+                .help("Input file with the new targets and cfg (JSON file)")
+                .required(true)
+                .takes_value(true))
+            .arg(Arg::with_name("pargs")
+                .help("New targeted program (USE_FAST) and arguments. Any \"@@\" will be substituted with the input filename from Angora.")
+                .required(true)
+                .multiple(true)
+                .allow_hyphen_values(true)
+                .last(true)
+                .index(1))
+            .arg(Arg::with_name("memory_limit")
+                .short("M")
+                .long("memory_limit")
+                .value_name("MEM")
+                .help("Memory limit for programs, default is 200(MB), set 0 for unlimit memory")
+                .takes_value(true))
+            .arg(Arg::with_name("time_limit")
+                .short("T")
+                .long("time_limit")
+                .value_name("TIME")
+                .help("time limit for programs: N seconds or Nms, default is 1(s)")
+                .takes_value(true)))
         .get_matches();
 
+    if let Some(matches) = matches.subcommand_matches("replay") {
+        replay_main(
+            matches.value_of("input_dir").unwrap(),
+            matches.value_of("output_dir").unwrap(),
+            matches.value_of("track_target").unwrap(),
+            matches.values_of_lossy("pargs").unwrap(),
+            value_t!(matches, "memory_limit", u64).unwrap_or(angora_common::config::MEM_LIMIT),
+            matches.value_of("time_limit"),
+            matches.value_of("cfg_file").unwrap(),
+        );
+        return;
+    }
+
     fuzz_main(
         matches.value_of("mode").unwrap_or("llvm"),
         matches.value_of("input_dir").unwrap(),
@@ -113,14 +224,25 @@ fn main() {
         matches.values_of_lossy("pargs").unwrap(),
         value_t!(matches, "thread_jobs", usize).unwrap_or(1),
         value_t!(matches, "memory_limit", u64).unwrap_or(angora_common::config::MEM_LIMIT),
-        value_t!(matches, "time_limit", u64).unwrap_or(angora_common::config::TIME_LIMIT),
+        matches.value_of("time_limit"),
         matches.value_of("search_method").unwrap_or("gd"),
-        matches.occurrences_of("sync_afl") > 0,
+        matches.value_of("distance").unwrap_or("harmonic"),
+        SyncOpt {
+            sync_afl: matches.occurrences_of("sync_afl") > 0,
+            instance: matches.value_of("main").or(matches.value_of("secondary")).map(String::from),
+            is_secondary: matches.is_present("secondary"),
+            sync_conds: matches.occurrences_of("sync_conds") > 0,
+            dirs: matches.values_of_lossy("sync_dir").unwrap_or_default(),
+            server: matches.value_of("sync_server").map(String::from),
+            import_cfgs: matches.values_of_lossy("import_cfg").unwrap_or_default(),
+            import_edges: matches.values_of_lossy("import_edges").unwrap_or_default(),
+        },
         matches.occurrences_of("disable_afl_mutation") == 0,
         matches.occurrences_of("disable_exploitation") == 0,
         matches.value_of("cfg_file").unwrap(),
         matches.value_of("sanopt_target"),
         matches.occurrences_of("only_directed") > 0,
         matches.value_of("num_of_func"),
+        matches.occurrences_of("shm_input") > 0,
     );
 }
@@ -0,0 +1,63 @@
+#[macro_use]
+extern crate clap;
+use clap::{App, Arg};
+
+extern crate angora;
+extern crate angora_common;
+use angora::inspect_main;
+
+fn main() {
+    let matches = App::new("parmesan-inspect")
+        .version(crate_version!())
+        .about("Explain a condition on an input: its operands and taint from tracking, and its objective value on the fast program.")
+        .arg(Arg::with_name("input_file")
+            .short("i")
+            .long("input")
+            .value_name("FILE")
+            .help("Sets the input")
+            .takes_value(true)
+            .required(true))
+        .arg(Arg::with_name("track_target")
+            .short("t")
+            .long("track")
+            .value_name("PROM")
+            .help("Sets the target (USE_TRACK) for tracking")
+            .takes_value(true)
+            .required(true))
+        .arg(Arg::with_name("cond")
+            .short("c")
+            .long("cond")
+            .value_name("CMPID[:CONTEXT[:ORDER]]")
+            .help("Sets the condition, all the contexts and orders of the cmpid if they are not given")
+            .takes_value(true)
+            .required(true))
+        .arg(Arg::with_name("pargs")
+            .help("Targeted program (USE_FAST) and arguments. Any \"@@\" will be substituted with the input filename.")
+            .required(true)
+            .multiple(true)
+            .allow_hyphen_values(true)
+            .last(true)
+            .index(1))
+        .arg(Arg::with_name("memory_limit")
+            .short("M")
+            .long("memory_limit")
+            .value_name("MEM")
+            .help("Memory limit for programs, default is 200(MB), set 0 for unlimit memory")
+            .takes_value(true))
+        .arg(Arg::with_name("time_limit")
+            .short("T")
+            .long("time_limit")
+            .value_name("TIME")
+            .help("time limit for programs: N seconds or Nms, default is 1(s), the tracking timeout is 12 * TIME")
+            .takes_value(true))
+        .get_matches();
+
+    inspect_main(
+        matches.value_of("input_file").unwrap(),
+        matches.value_of("track_target").unwrap(),
+        matches.values_of_lossy("pargs").unwrap(),
+        value_t!(matches, "memory_limit", u64).unwrap_or(angora_common::config::MEM_LIMIT),
+        matches.value_of("time_limit"),
+        matches.value_of("cond").unwrap(),
+    );
+}
@@ -0,0 +1,61 @@
+#[macro_use]
+extern crate clap;
+use clap::{App, Arg};
+
+extern crate angora;
+extern crate angora_common;
+use angora::showmap_main;
+
+fn main() {
+    let matches = App::new("parmesan-showmap")
+        .version(crate_version!())
+        .about("Print the edges hit by inputs with their hit-count buckets, and the targets reachable from them.")
+        .arg(Arg::with_name("input")
+            .short("i")
+            .long("input")
+            .value_name("FILE_OR_DIR")
+            .help("Sets the input, or a directory of inputs")
+            .takes_value(true)
+            .required(true))
+        .arg(Arg::with_name("output_file")
+            .short("o")
+            .long("output")
+            .value_name("FILE")
+            .help("Sets the output file, default is stdout")
+            .takes_value(true))
+        .arg(Arg::with_name("pargs")
+            .help("Targeted program (USE_FAST) and arguments. Any \"@@\" will be substituted with the input filename.")
+            .required(true)
+            .multiple(true)
+            .allow_hyphen_values(true)
+            .last(true)
+            .index(1))
+        .arg(Arg::with_name("memory_limit")
+            .short("M")
+            .long("memory_limit")
+            .value_name("MEM")
+            .help("Memory limit for programs, default is 200(MB), set 0 for unlimit memory")
+            .takes_value(true))
+        .arg(Arg::with_name("time_limit")
+            .short("T")
+            .long("time_limit")
+            .value_name("TIME")
+            .help("time limit for programs: N seconds or Nms, default is 1(s)")
+            .takes_value(true))
+        .arg(Arg::with_name("cfg_file")
+            .short("c")
+            .long("cfg")
+            .value_name("TARGET_FILE")
+            .help("Input file with targets and cfg (JSON file), to show the targets reachable from each edge")
+            .takes_value(true))
+        .get_matches();
+
+    showmap_main(
+        matches.value_of("input").unwrap(),
+        matches.value_of("output_file"),
+        matches.values_of_lossy("pargs").unwrap(),
+        value_t!(matches, "memory_limit", u64).unwrap_or(angora_common::config::MEM_LIMIT),
+        matches.value_of("time_limit"),
+        matches.value_of("cfg_file"),
+    );
+}
@@ -0,0 +1,21 @@
+#[macro_use]
+extern crate clap;
+use clap::{App, Arg};
+
+extern crate angora;
+use angora::server_main;
+
+fn main() {
+    let matches = App::new("parmesan-sync-server")
+        .version(crate_version!())
+        .about("Sync server of a distributed campaign: the fuzzers started with --sync_server push their new inputs and what their cfg learned, and pull the others'.")
+        .arg(Arg::with_name("listen")
+            .short("l")
+            .long("listen")
+            .value_name("ADDR")
+            .help("Sets the address to listen on, default is 127.0.0.1:7878")
+            .takes_value(true))
+        .get_matches();
+
+    server_main(matches.value_of("listen").unwrap_or("127.0.0.1:7878"));
+}
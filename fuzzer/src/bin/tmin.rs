@@ -0,0 +1,72 @@
+#[macro_use]
+extern crate clap;
+use clap::{App, Arg};
+
+extern crate angora;
+extern crate angora_common;
+use angora::tmin_main;
+
+fn main() {
+    let matches = App::new("angora-tmin")
+        .version(crate_version!())
+        .about("Minimize an input: keep the same crash, the same edges, or reaching a target condition.")
+        .arg(Arg::with_name("input_file")
+            .short("i")
+            .long("input")
+            .value_name("FILE")
+            .help("Sets the input to minimize")
+            .takes_value(true)
+            .required(true))
+        .arg(Arg::with_name("output_file")
+            .short("o")
+            .long("output")
+            .value_name("FILE")
+            .help("Sets the file for the minimized input, default is the input file with .min appended")
+            .takes_value(true))
+        .arg(Arg::with_name("pargs")
+            .help("Targeted program (USE_FAST) and arguments. Any \"@@\" will be substituted with the input filename.")
+            .required(true)
+            .multiple(true)
+            .allow_hyphen_values(true)
+            .last(true)
+            .index(1))
+        .arg(Arg::with_name("memory_limit")
+            .short("M")
+            .long("memory_limit")
+            .value_name("MEM")
+            .help("Memory limit for programs, default is 200(MB), set 0 for unlimit memory")
+            .takes_value(true))
+        .arg(Arg::with_name("time_limit")
+            .short("T")
+            .long("time_limit")
+            .value_name("TIME")
+            .help("time limit for programs: N seconds or Nms, default is 1(s)")
+            .takes_value(true))
+        .arg(Arg::with_name("target")
+            .short("t")
+            .long("target")
+            .value_name("CMPID[:CONTEXT]")
+            .help("Keep reaching this condition, instead of keeping the crash or the edges")
+            .takes_value(true))
+        .arg(Arg::with_name("track_target")
+            .long("track")
+            .value_name("PROM")
+            .help("Sets the target (USE_TRACK) to find the context of the -t condition if it is not given")
+            .takes_value(true))
+        .arg(Arg::with_name("edges")
+            .short("e")
+            .long("edges")
+            .help("Keep the edges even if the input crashes"))
+        .get_matches();
+
+    tmin_main(
+        matches.value_of("input_file").unwrap(),
+        matches.value_of("output_file"),
+        matches.values_of_lossy("pargs").unwrap(),
+        value_t!(matches, "memory_limit", u64).unwrap_or(angora_common::config::MEM_LIMIT),
+        matches.value_of("time_limit"),
+        matches.value_of("target"),
+        matches.value_of("track_target"),
+        matches.occurrences_of("edges") > 0,
+    );
+}
@@ -1,7 +1,9 @@
 use crate::executor::StatusType;
-use angora_common::{config::BRANCHES_SIZE, shm::SHM};
+use angora_common::{config::BRANCHES_SIZE, defs, shm::SHM};
 use std::{
-    self,
+    self, fs, io,
+    io::prelude::*,
+    path::Path,
     sync::{
         atomic::{AtomicUsize, Ordering},
         Arc, RwLock,
@@ -10,7 +12,7 @@ use std::{
 #[cfg(feature = "unstable")]
 use std::intrinsics::unlikely;
 
-use crate::dyncfg::cfg::{ControlFlowGraph, CmpId};
+use crate::dyncfg::cfg::{CmpId, SharedCfg, SharedReaching};
 
 pub type BranchBuf = [u8; BRANCHES_SIZE];
 #[cfg(target_pointer_width = "32")]
@@ -46,22 +48,73 @@ macro_rules! cast {
     }};
 }
 
+// Allocate on the heap directly, the buffer is too large for the stack of a debug build.
+fn new_branch_buf() -> Box<BranchBuf> {
+    let buf = vec![255u8; BRANCHES_SIZE].into_boxed_slice();
+    std::convert::TryInto::try_into(buf).unwrap()
+}
+
+// The hit edges of a branch map, with their counts bucketed by COUNT_LOOKUP.
+pub fn get_path(buf: &BranchBuf) -> Vec<(usize, u8)> {
+    let mut path = Vec::<(usize, u8)>::new();
+    let buf_plus: &BranchBufPlus = cast!(buf);
+    for (i, &v) in buf_plus.iter().enumerate() {
+        macro_rules! run_loop { () => {{
+            let base = i * ENTRY_SIZE;
+            for j in 0..ENTRY_SIZE {
+                let idx = base + j;
+                let new_val = buf[idx];
+                if new_val > 0 {
+                    path.push((idx, COUNT_LOOKUP[new_val as usize]))
+                }
+            }
+        }}}
+        #[cfg(feature = "unstable")]
+        {
+            if unsafe { unlikely(v > 0) } {
+                run_loop!()
+            }
+        }
+        #[cfg(not(feature = "unstable"))]
+        {
+            if v > 0 {
+                run_loop!()
+            }
+        }
+    }
+    // debug!("count branch table: {}", path.len());
+    path
+}
+
 pub struct GlobalBranches {
     virgin_branches: RwLock<Box<BranchBuf>>,
     tmouts_branches: RwLock<Box<BranchBuf>>,
     crashes_branches: RwLock<Box<BranchBuf>>,
     density: AtomicUsize,
-    cfg: RwLock<ControlFlowGraph>,
+    cfg: SharedCfg,
+    reaching: SharedReaching,
+}
+
+// The edges hit at least once, in a virgin map.
+pub fn covered_edges(virgin: &[u8]) -> Vec<usize> {
+    virgin
+        .iter()
+        .enumerate()
+        .filter(|&(_, &v)| v != 255u8)
+        .map(|(i, _)| i)
+        .collect()
 }
 
 impl GlobalBranches {
-    pub fn new(cfg: RwLock<ControlFlowGraph>) -> Self {
+    pub fn new(cfg: SharedCfg) -> Self {
+        let reaching = cfg.read().unwrap().get_reaching();
         Self {
-            virgin_branches: RwLock::new(Box::new([255u8; BRANCHES_SIZE])),
-            tmouts_branches: RwLock::new(Box::new([255u8; BRANCHES_SIZE])),
-            crashes_branches: RwLock::new(Box::new([255u8; BRANCHES_SIZE])),
+            virgin_branches: RwLock::new(new_branch_buf()),
+            tmouts_branches: RwLock::new(new_branch_buf()),
+            crashes_branches: RwLock::new(new_branch_buf()),
             density: AtomicUsize::new(0),
             cfg,
+            reaching,
         }
     }
 
@@ -69,6 +122,44 @@ impl GlobalBranches {
         let d = self.density.load(Ordering::Relaxed);
         (d * 10000 / BRANCHES_SIZE) as f32 / 100.0
     }
+
+    pub fn get_covered_edges(&self) -> Vec<usize> {
+        covered_edges(&self.virgin_branches.read().unwrap()[..])
+    }
+
+    fn bitmaps(&self) -> [(&RwLock<Box<BranchBuf>>, &str); 3] {
+        [
+            (&self.virgin_branches, defs::VIRGIN_BRANCHES_FILE),
+            (&self.tmouts_branches, defs::TMOUTS_BRANCHES_FILE),
+            (&self.crashes_branches, defs::CRASHES_BRANCHES_FILE),
+        ]
+    }
+
+    pub fn save_bitmaps(&self, dir: &Path) -> io::Result<()> {
+        for (map, name) in self.bitmaps().iter() {
+            let buf = map.read().unwrap();
+            let mut f = fs::File::create(dir.join(name))?;
+            f.write_all(&buf[..])?;
+        }
+        Ok(())
+    }
+
+    pub fn load_bitmaps(&self, dir: &Path) -> io::Result<()> {
+        for (map, name) in self.bitmaps().iter() {
+            let mut f = fs::File::open(dir.join(name))?;
+            let mut buf = map.write().unwrap();
+            f.read_exact(&mut buf[..])?;
+        }
+        let density = self
+            .virgin_branches
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|&&v| v != 255u8)
+            .count();
+        self.density.store(density, Ordering::Relaxed);
+        Ok(())
+    }
 }
 
 pub struct Branches {
@@ -91,35 +182,17 @@ impl Branches {
     }
 
     fn get_path(&self) -> Vec<(usize, u8)> {
-        let mut path = Vec::<(usize, u8)>::new();
-        let buf_plus: &BranchBufPlus = cast!(&*self.trace);
-        let buf: &BranchBuf = &*self.trace;
-        for (i, &v) in buf_plus.iter().enumerate() {
-            macro_rules! run_loop { () => {{
-                let base = i * ENTRY_SIZE;
-                for j in 0..ENTRY_SIZE {
-                    let idx = base + j;
-                    let new_val = buf[idx];
-                    if new_val > 0 {
-                        path.push((idx, COUNT_LOOKUP[new_val as usize]))
-                    }
-                }
-            }}}
-            #[cfg(feature = "unstable")]
-            {
-                if unsafe { unlikely(v > 0) } {
-                    run_loop!()
-                }
-            }
-            #[cfg(not(feature = "unstable"))]
-            {
-                if v > 0 {
-                    run_loop!()
-                }
-            }
-        }
-        // debug!("count branch table: {}", path.len());
-        path
+        get_path(&*self.trace)
+    }
+
+    // The targets on the path of the last run.
+    pub fn get_reached_targets(&self) -> Vec<CmpId> {
+        let cfg = self.global.cfg.read().unwrap();
+        self.get_path()
+            .into_iter()
+            .map(|(idx, _)| idx as CmpId)
+            .filter(|&cmp| cfg.is_target(cmp))
+            .collect()
     }
 
     pub fn has_new(&mut self, status: StatusType, directed: bool) -> (bool, bool, usize) {
@@ -183,17 +256,16 @@ impl Branches {
             }
         }
 
-        let mut has_new_directed_edge = false;
-        for &br in &to_write {
-            let dyncfg = self.global.cfg.read().unwrap();
-            if dyncfg.has_path_to_target(br.0 as CmpId) {
-                has_new_directed_edge = true;
-                break;
-            }
+        if !directed {
+            return (true, has_new_edge, edge_num);
         }
+        let has_new_directed_edge = {
+            let reaching = self.global.reaching.read().unwrap();
+            to_write.iter().any(|&br| reaching.contains(&(br.0 as CmpId)))
+        };
 
         //(has_new_directed_edge, has_new_edge, edge_num)
-        (if !directed {true} else {has_new_directed_edge}, has_new_edge, edge_num)
+        (has_new_directed_edge, has_new_edge, edge_num)
     }
 }
 
@@ -206,23 +278,54 @@ impl std::fmt::Debug for Branches {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::dyncfg::{cfg::ControlFlowGraph, fparse::CfgFile};
+    use std::collections::{HashMap, HashSet};
+
+    fn empty_global_branches() -> Arc<GlobalBranches> {
+        let cfg = ControlFlowGraph::new(CfgFile {
+            targets: HashSet::new(),
+            edges: HashSet::new(),
+            callsite_dominators: HashMap::new(),
+        });
+        Arc::new(GlobalBranches::new(Arc::new(RwLock::new(cfg))))
+    }
+
+    #[test]
+    fn learned_edges_are_shared() {
+        let cfg = Arc::new(RwLock::new(ControlFlowGraph::new(CfgFile {
+            targets: [2].iter().cloned().collect(),
+            edges: HashSet::new(),
+            callsite_dominators: HashMap::new(),
+        })));
+        let gb = GlobalBranches::new(cfg.clone());
+        assert!(!gb.cfg.read().unwrap().has_path_to_target(1));
+        // e.g. by Executor::track through the depot
+        cfg.write().unwrap().add_edge((1, 2));
+        assert!(gb.cfg.read().unwrap().has_path_to_target(1));
+        // has_new looks them up without the cfg lock.
+        let track = cfg.write().unwrap();
+        assert!(gb.reaching.read().unwrap().contains(&1));
+        drop(track);
+        cfg.write().unwrap().remove_target(2);
+        assert!(!gb.reaching.read().unwrap().contains(&1));
+    }
 
     #[test]
     #[ignore]
     fn branch_empty() {
-        let global_branches = Arc::new(GlobalBranches::new());
+        let global_branches = empty_global_branches();
         let mut br = Branches::new(global_branches);
-        assert_eq!(br.has_new(StatusType::Normal), (false, false, 0));
-        assert_eq!(br.has_new(StatusType::Timeout), (false, false, 0));
-        assert_eq!(br.has_new(StatusType::Crash), (false, false, 0));
+        assert_eq!(br.has_new(StatusType::Normal, false), (false, false, 0));
+        assert_eq!(br.has_new(StatusType::Timeout, false), (false, false, 0));
+        assert_eq!(br.has_new(StatusType::Crash, false), (false, false, 0));
     }
 
     #[test]
     #[ignore]
     fn branch_find_new() {
-        let global_branches = Arc::new(GlobalBranches::new());
+        let global_branches = empty_global_branches();
         let mut br = Branches::new(global_branches);
-        assert_eq!(br.has_new(StatusType::Normal), (false, false, 0));
+        assert_eq!(br.has_new(StatusType::Normal, false), (false, false, 0));
         {
             let trace = &mut br.trace;
             trace[4] = 1;
@@ -232,6 +335,29 @@ mod tests {
         let path = br.get_path();
         assert_eq!(path.len(), 3);
         assert_eq!(path[2].1, COUNT_LOOKUP[3]);
-        assert_eq!(br.has_new(StatusType::Normal), (true, true, 3));
+        assert_eq!(br.has_new(StatusType::Normal, false), (true, true, 3));
+    }
+
+    #[test]
+    fn bitmaps_save_load() {
+        let dir = std::env::temp_dir().join(format!("parmesan_bitmaps_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let gb = empty_global_branches();
+        {
+            let mut virgin = gb.virgin_branches.write().unwrap();
+            virgin[7] = 0;
+            virgin[42] = 254;
+        }
+        gb.crashes_branches.write().unwrap()[3] = 1;
+        gb.save_bitmaps(&dir).unwrap();
+
+        let loaded = empty_global_branches();
+        loaded.load_bitmaps(&dir).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(loaded.virgin_branches.read().unwrap()[42], 254);
+        assert_eq!(loaded.crashes_branches.read().unwrap()[3], 1);
+        assert_eq!(loaded.tmouts_branches.read().unwrap()[3], 255);
+        assert_eq!(loaded.density.load(Ordering::Relaxed), 2);
+        assert_eq!(loaded.get_covered_edges(), vec![7, 42]);
     }
 }
@@ -13,6 +13,10 @@ You can modify /proc/sys/kernel/core_pattern to disable it by:
 
 static CORE_PATTERN_FILE: &str = "/proc/sys/kernel/core_pattern";
 
+// Injected by __ANGORA_LOOP, see llvm_mode/include/defs.h.
+// Not in angora_common, whose strings end up in every program linked with the runtime.
+static PERSIST_SIG: &str = "##SIG_ANGORA_PERSISTENT##";
+
 fn check_crash_handling() {
     let mut f = File::open(CORE_PATTERN_FILE).unwrap();
     let mut buffer = String::new();
@@ -48,11 +52,28 @@ pub fn check_asan(target: &str) -> bool {
     containt_string(&f_data, "libasan.so") || containt_string(&f_data, "__msan_init")
 }
 
+pub fn check_persistent(target: &str) -> bool {
+    let f_data = mmap_file(target);
+    containt_string(&f_data, PERSIST_SIG)
+}
+
+// Built with the fork server in the track runtime (runtime/src/forkcli.rs)?
+pub fn check_track_forksrv(target: &str) -> bool {
+    let f_data = mmap_file(target);
+    containt_string(&f_data, "__angora_start_track_forkcli")
+}
+
+// Built by afl-clang-fast (or afl-clang-lto) instead of Angora?
+pub fn check_afl(target: &str) -> bool {
+    let f_data = mmap_file(target);
+    !containt_string(&f_data, "__angora_cond_cmpid") && containt_string(&f_data, "__AFL_SHM_ID")
+}
+
 fn check_fast(target: &str) {
     check_target_binary(target);
     let f_data = mmap_file(target);
-    if !containt_string(&f_data, "__angora_cond_cmpid") {
-        panic!("The program is not complied by Angora");
+    if !containt_string(&f_data, "__angora_cond_cmpid") && !check_afl(target) {
+        panic!("The program is not complied by Angora or AFL");
     }
 }
 
@@ -0,0 +1,150 @@
+// Corpus minimization: keep the smallest set of inputs that covers the same
+// edges and hit-count buckets (see COUNT_LOOKUP) as the whole corpus.
+use crate::{
+    command,
+    depot::{list_files, read_from_file},
+    dyncfg::{
+        cfg::{CmpId, ControlFlowGraph},
+        fparse::parse_targets_file,
+    },
+    executor::{Runner, StatusType},
+};
+use std::{
+    cmp::Reverse,
+    collections::{HashMap, HashSet},
+    fs,
+    path::Path,
+};
+
+pub struct CminEntry {
+    // edge index * 8 + bucket bit
+    pub tuples: Vec<usize>,
+    pub size: usize,
+    // Number of targets on the path, with -c.
+    pub target_hits: usize,
+}
+
+fn path_tuples(path: &[(usize, u8)]) -> Vec<usize> {
+    path.iter()
+        .map(|&(idx, bucket)| idx * 8 + bucket.trailing_zeros() as usize)
+        .collect()
+}
+
+// Greedy set cover: for the rarest tuple not covered yet, take the best input that has it.
+// Inputs hitting more targets are the best, then the smaller ones.
+pub fn minimize(entries: &[CminEntry]) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..entries.len()).collect();
+    order.sort_by_key(|&i| (Reverse(entries[i].target_hits), entries[i].size, i));
+
+    let mut best = HashMap::new();
+    let mut counts = HashMap::new();
+    for &i in &order {
+        for &t in &entries[i].tuples {
+            best.entry(t).or_insert(i);
+            *counts.entry(t).or_insert(0usize) += 1;
+        }
+    }
+
+    let mut tuples: Vec<usize> = counts.keys().cloned().collect();
+    tuples.sort_by_key(|t| (counts[t], *t));
+
+    let mut covered = HashSet::new();
+    let mut selected = vec![];
+    for t in tuples {
+        if covered.contains(&t) {
+            continue;
+        }
+        let i = best[&t];
+        covered.extend(entries[i].tuples.iter().cloned());
+        selected.push(i);
+    }
+    selected.sort();
+    selected
+}
+
+pub fn cmin_main(
+    in_dir: &str,
+    out_dir: &str,
+    pargs: Vec<String>,
+    mem_limit: u64,
+    time_limit: Option<&str>,
+    cfg_file: Option<&str>,
+) {
+    pretty_env_logger::init();
+
+    let out_dir = Path::new(out_dir);
+    if out_dir.exists() && out_dir.read_dir().map(|mut d| d.next().is_some()).unwrap_or(true) {
+        panic!("The output directory {:?} is not empty", out_dir);
+    }
+    let time_limit = command::parse_tool_time_limit(time_limit);
+    let cfg = cfg_file.map(|f| {
+        ControlFlowGraph::new(parse_targets_file(Path::new(f)).expect("Could not read cfg targets file"))
+    });
+
+    let inputs = list_files(Path::new(in_dir));
+    let mut runner = Runner::new(pargs, time_limit, mem_limit);
+    let mut entries = vec![];
+    let mut paths = vec![];
+    let mut num_skipped = 0;
+    for path in &inputs {
+        let buf = read_from_file(path);
+        let status = runner.run(&buf);
+        if status != StatusType::Normal {
+            warn!("Skip {:?}: {:?}", path, status);
+            num_skipped += 1;
+            continue;
+        }
+        let trace = runner.get_path();
+        let target_hits = match &cfg {
+            Some(cfg) => trace
+                .iter()
+                .filter(|&&(idx, _)| cfg.is_target(idx as CmpId))
+                .count(),
+            None => 0,
+        };
+        entries.push(CminEntry {
+            tuples: path_tuples(&trace),
+            size: buf.len(),
+            target_hits,
+        });
+        paths.push(path);
+    }
+
+    let selected = minimize(&entries);
+    fs::create_dir_all(out_dir).expect("Could not create the output directory");
+    for &i in &selected {
+        let path = paths[i];
+        fs::copy(path, out_dir.join(path.file_name().unwrap())).expect("Could not copy input");
+    }
+    let num_tuples: HashSet<usize> = entries.iter().flat_map(|e| e.tuples.iter().cloned()).collect();
+    println!(
+        "Kept {} of {} inputs ({} tuples), skipped {} crashing or hanging inputs",
+        selected.len(),
+        inputs.len(),
+        num_tuples.len(),
+        num_skipped
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(tuples: &[usize], size: usize, target_hits: usize) -> CminEntry {
+        CminEntry {
+            tuples: tuples.to_vec(),
+            size,
+            target_hits,
+        }
+    }
+
+    #[test]
+    fn minimize_keeps_coverage() {
+        let entries = vec![entry(&[1, 2], 10, 0), entry(&[2, 3], 10, 0), entry(&[1, 2, 3], 100, 0)];
+        assert_eq!(minimize(&entries), vec![0, 1]);
+        // The input reaching a target wins, even if it is larger.
+        let entries = vec![entry(&[1, 2], 10, 0), entry(&[2, 3], 10, 0), entry(&[1, 2, 3], 100, 1)];
+        assert_eq!(minimize(&entries), vec![2]);
+        assert_eq!(path_tuples(&[(1, 1), (2, 128)]), vec![8, 23]);
+    }
+}
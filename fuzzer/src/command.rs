@@ -1,7 +1,7 @@
 use crate::{check_dep, search, tmpfs};
-use angora_common::defs;
+use angora_common::{config, defs};
 use std::{
-    env,
+    cmp, env,
     path::{Path, PathBuf},
     process::Command,
 };
@@ -9,6 +9,7 @@ use std::{
 static TMP_DIR: &str = "tmp";
 static INPUT_FILE: &str = "cur_input";
 static FORKSRV_SOCKET_FILE: &str = "forksrv_socket";
+static TRACK_FORKSRV_SOCKET_FILE: &str = "track_forksrv_socket";
 static TRACK_FILE: &str = "track";
 static PIN_ROOT_VAR: &str = "PIN_ROOT";
 
@@ -37,20 +38,35 @@ pub struct CommandOpt {
     pub mode: InstrumentationMode,
     pub id: usize,
     pub main: (String, Vec<String>),
+    // The fast program, also in the sanopt copy, to verify crashes on it.
+    pub fast: (String, Vec<String>),
+    pub fast_uses_asan: bool,
     pub track: (String, Vec<String>),
     pub tmp_dir: PathBuf,
     pub out_file: String,
     pub forksrv_socket_path: String,
+    pub track_forksrv_socket_path: String,
+    pub enable_track_forksrv: bool,
     pub track_path: String,
     pub is_stdin: bool,
+    pub shm_input: bool,
     pub search_method: search::SearchMethod,
     pub mem_limit: u64,
+    // ms
     pub time_limit: u64,
+    pub time_limit_track: u64,
+    pub time_limit_sanopt: u64,
+    pub auto_time_limit: bool,
     pub is_raw: bool,
     pub uses_asan: bool,
+    pub is_persistent: bool,
+    // The main program is built by AFL, and runs under AFL's fork server.
+    pub afl_forksrv: bool,
     pub ld_library: String,
     pub enable_afl: bool,
     pub enable_exploitation: bool,
+    // Run the deterministic stage of the conditions, off for secondary instances.
+    pub enable_det: bool,
     pub directed_targets_file: String,
     pub sanopt_bin: Option<String>,
     pub directed_only: bool,
@@ -65,6 +81,33 @@ pub fn make_absolute_str(path_str: &str) -> String {
     path.to_path_buf().to_str().unwrap().to_string()
 }
 
+// -T: N (seconds), Nms, or auto. Returns the limit in ms, or None for auto.
+pub fn parse_time_limit(s: &str) -> Option<u64> {
+    if s == "auto" {
+        return None;
+    }
+    let time_limit = match s.strip_suffix("ms") {
+        Some(ms) => ms.parse::<u64>().ok(),
+        None => s.parse::<u64>().ok().map(|secs| secs * 1000),
+    };
+    match time_limit {
+        Some(t) if t > 0 => Some(t),
+        _ => panic!("Invalid time limit {:?}, use N (seconds), Nms or auto", s),
+    }
+}
+
+// -T of the tools, which have no dry run to calibrate -T auto.
+pub fn parse_tool_time_limit(time_limit: Option<&str>) -> u64 {
+    match time_limit.map(parse_time_limit) {
+        Some(Some(t)) => t,
+        None => config::TIME_LIMIT,
+        Some(None) => {
+            warn!("-T auto is not supported here, use {} ms", config::TIME_LIMIT);
+            config::TIME_LIMIT
+        },
+    }
+}
+
 impl CommandOpt {
     pub fn new(
         mode: &str,
@@ -73,12 +116,13 @@ impl CommandOpt {
         out_dir: &Path,
         search_method: &str,
         mut mem_limit: u64,
-        time_limit: u64,
+        time_limit: Option<&str>,
         enable_afl: bool,
         enable_exploitation: bool,
         directed_targets_file: &str,
         sanopt_target: Option<&str>,
         directed_only: bool,
+        shm_input: bool,
     ) -> Self {
         let mode = InstrumentationMode::from(mode);
         
@@ -91,6 +135,11 @@ impl CommandOpt {
             .to_str()
             .unwrap()
             .to_owned();
+        let track_forksrv_socket_path = tmp_dir
+            .join(TRACK_FORKSRV_SOCKET_FILE)
+            .to_str()
+            .unwrap()
+            .to_owned();
 
         let track_path = tmp_dir.join(TRACK_FILE).to_str().unwrap().to_owned();
 
@@ -117,6 +166,14 @@ impl CommandOpt {
             warn!("The program compiled with ASAN, set MEM_LIMIT to 0 (unlimited)");
             mem_limit = 0;
         }
+        let is_persistent = check_dep::check_persistent(&main_bin);
+        if is_persistent {
+            info!("The program uses __ANGORA_LOOP, run it in persistent mode");
+        }
+        let afl_forksrv = check_dep::check_afl(&main_bin);
+        if afl_forksrv {
+            warn!("The program is built by AFL: it only gives coverage, constraints are not solved with gradients");
+        }
 
         let track_bin;
         let mut track_args = Vec::<String>::new();
@@ -144,35 +201,79 @@ impl CommandOpt {
             track_args = main_args.clone();
         }
         let sanopt_bin = sanopt_target.map(|s| s.to_string());
+        let (time_limit, auto_time_limit) = match time_limit.map(parse_time_limit) {
+            None => (config::TIME_LIMIT, false),
+            Some(Some(t)) => (t, false),
+            // Calibrated after the dry run.
+            Some(None) => (config::AUTO_TIME_LIMIT_MAX, true),
+        };
+        let enable_track_forksrv = !mode.is_pin_mode() && check_dep::check_track_forksrv(&track_bin);
+        if !enable_track_forksrv {
+            warn!("The track program is built without fork server, tracking will be slow");
+        }
 
         Self {
             mode,
             id: 0,
-            main: (main_bin, main_args),
+            main: (main_bin.clone(), main_args.clone()),
+            fast: (main_bin, main_args),
+            fast_uses_asan: uses_asan,
             track: (track_bin, track_args),
             tmp_dir,
             out_file: out_file,
             forksrv_socket_path,
+            track_forksrv_socket_path,
+            enable_track_forksrv,
             track_path,
             is_stdin: !has_input_arg,
+            shm_input: shm_input && !afl_forksrv,
             search_method: search::parse_search_method(search_method),
             mem_limit,
             time_limit,
+            time_limit_track: Self::track_time_limit(time_limit),
+            time_limit_sanopt: time_limit * config::TIME_LIMIT_SANOPT_FACTOR,
+            auto_time_limit,
             uses_asan,
+            is_persistent,
+            afl_forksrv,
             is_raw: true,
             ld_library,
             enable_afl,
             enable_exploitation,
+            enable_det: true,
             directed_targets_file: directed_targets_file.to_string(),
             sanopt_bin,
             directed_only,
         }
     }
 
+    fn track_time_limit(time_limit: u64) -> u64 {
+        cmp::max(time_limit * config::TIME_LIMIT_TRACK_FACTOR, config::TIME_LIMIT_TRACK)
+    }
+
+    pub fn set_time_limit(&mut self, time_limit: u64) {
+        self.time_limit = time_limit;
+        self.time_limit_track = Self::track_time_limit(time_limit);
+        self.time_limit_sanopt = time_limit * config::TIME_LIMIT_SANOPT_FACTOR;
+    }
+
+    // -T auto: a multiple of the slowest seed in the dry run.
+    pub fn calibrate_time_limit(&mut self, slowest_exec_us: u32) {
+        let time_limit = (slowest_exec_us as u64 * config::AUTO_TIME_LIMIT_FACTOR / 1000)
+            .clamp(config::AUTO_TIME_LIMIT_MIN, config::AUTO_TIME_LIMIT_MAX);
+        info!(
+            "Auto time limit: {} ms (slowest seed: {} us)",
+            time_limit, slowest_exec_us
+        );
+        self.set_time_limit(time_limit);
+    }
+
     pub fn specify(&self, id: usize) -> Self {
         let mut cmd_opt = self.clone();
         let new_file = format!("{}_{}", &cmd_opt.out_file, id);
         let new_forksrv_socket_path = format!("{}_{}", &cmd_opt.forksrv_socket_path, id);
+        let new_track_forksrv_socket_path =
+            format!("{}_{}", &cmd_opt.track_forksrv_socket_path, id);
         let new_track_path = format!("{}_{}", &cmd_opt.track_path, id);
         if !self.is_stdin {
             for arg in &mut cmd_opt.main.1 {
@@ -180,6 +281,11 @@ impl CommandOpt {
                     *arg = new_file.clone();
                 }
             }
+            for arg in &mut cmd_opt.fast.1 {
+                if arg == "@@" {
+                    *arg = new_file.clone();
+                }
+            }
             for arg in &mut cmd_opt.track.1 {
                 if arg == "@@" {
                     *arg = new_file.clone();
@@ -189,6 +295,7 @@ impl CommandOpt {
         cmd_opt.id = id;
         cmd_opt.out_file = new_file.to_owned();
         cmd_opt.forksrv_socket_path = new_forksrv_socket_path.to_owned();
+        cmd_opt.track_forksrv_socket_path = new_track_forksrv_socket_path.to_owned();
         cmd_opt.track_path = new_track_path.to_owned();
         cmd_opt.is_raw = false;
         cmd_opt
@@ -202,15 +309,35 @@ impl CommandOpt {
         } else {
             self.main.0.clone()
         };
+        cmd_opt.is_persistent = check_dep::check_persistent(&bin);
+        cmd_opt.afl_forksrv = check_dep::check_afl(&bin);
+        // AFL programs read the input file.
+        cmd_opt.shm_input = self.shm_input && !cmd_opt.afl_forksrv;
         cmd_opt.main = (bin, main_args);
         let new_file =  format!("{}_{}", &self.out_file, "sanopt");
         let new_forksrv_socket_path = format!("{}_{}", &self.forksrv_socket_path, "sanopt");
+        let new_track_forksrv_socket_path =
+            format!("{}_{}", &self.track_forksrv_socket_path, "sanopt");
         let new_track_path = format!("{}_{}", &cmd_opt.track_path, "sanopt");
+        // The programs read the input file of this copy.
+        for arg in cmd_opt
+            .main
+            .1
+            .iter_mut()
+            .chain(cmd_opt.fast.1.iter_mut())
+            .chain(cmd_opt.track.1.iter_mut())
+        {
+            if *arg == self.out_file {
+                *arg = new_file.clone();
+            }
+        }
         cmd_opt.out_file = new_file.to_owned();
         cmd_opt.forksrv_socket_path = new_forksrv_socket_path.to_owned();
+        cmd_opt.track_forksrv_socket_path = new_track_forksrv_socket_path.to_owned();
         cmd_opt.track_path = new_track_path.to_owned();
         cmd_opt.uses_asan = true;
         cmd_opt.mem_limit = 0;
+        cmd_opt.time_limit = self.time_limit_sanopt;
         cmd_opt
     }
 }
@@ -222,3 +349,21 @@ impl Drop for CommandOpt {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn time_limit() {
+        assert_eq!(parse_time_limit("2"), Some(2000));
+        assert_eq!(parse_time_limit("50ms"), Some(50));
+        assert_eq!(parse_time_limit("auto"), None);
+    }
+
+    #[test]
+    #[should_panic]
+    fn time_limit_invalid() {
+        parse_time_limit("0ms");
+    }
+}
@@ -1,6 +1,7 @@
 use super::*;
-use crate::{cond_stmt::CondStmt, executor::StatusType};
-use crate::dyncfg::cfg::ControlFlowGraph;
+use crate::{cond_stmt::CondStmt, executor::StatusType, stats::TargetStats};
+use crate::dyncfg::cfg::SharedCfg;
+use crate::triage::BugSet;
 use rand;
 use std::{
     fs,
@@ -9,7 +10,7 @@ use std::{
     path::{Path, PathBuf},
     sync::{
         atomic::{AtomicUsize, Ordering},
-        Mutex, RwLock, Arc
+        Mutex, Arc
     },
 };
 // https://crates.io/crates/priority-queue
@@ -21,19 +22,37 @@ pub struct Depot {
     pub num_inputs: AtomicUsize,
     pub num_hangs: AtomicUsize,
     pub num_crashes: AtomicUsize,
+    pub num_flaky: AtomicUsize,
+    pub bugs: BugSet,
     pub dirs: DepotDir,
-    pub cfg: RwLock<ControlFlowGraph>,
+    pub cfg: SharedCfg,
+    pub targets: TargetStats,
 }
 
 impl Depot {
-    pub fn new(in_dir: PathBuf, out_dir: &Path, cfg: RwLock<ControlFlowGraph>) -> Self {
+    pub fn new(in_dir: PathBuf, out_dir: &Path, cfg: SharedCfg) -> Self {
+        let dirs = DepotDir::new(in_dir, out_dir);
+        // Continue numbering after the files of a resumed campaign.
+        let num_inputs = next_file_id(&dirs.inputs_dir);
+        let num_hangs = next_file_id(&dirs.hangs_dir);
+        let num_crashes = next_file_id(&dirs.crashes_dir);
+        let num_flaky = next_file_id(&dirs.flaky_dir);
+        let bugs = BugSet::load(&dirs.crashes_dir, &dirs.flaky_dir);
+        let targets = TargetStats::new(cfg.read().unwrap().get_targets());
+        // e.g. from a resumed or imported cfg.
+        for t in cfg.read().unwrap().get_solved_targets() {
+            targets.solve(t);
+        }
         Self {
             queue: Mutex::new(PriorityQueue::new()),
-            num_inputs: AtomicUsize::new(0),
-            num_hangs: AtomicUsize::new(0),
-            num_crashes: AtomicUsize::new(0),
-            dirs: DepotDir::new(in_dir, out_dir),
-            cfg
+            num_inputs: AtomicUsize::new(num_inputs),
+            num_hangs: AtomicUsize::new(num_hangs),
+            num_crashes: AtomicUsize::new(num_crashes),
+            num_flaky: AtomicUsize::new(num_flaky),
+            bugs,
+            dirs,
+            cfg,
+            targets,
         }
     }
 
@@ -78,6 +97,16 @@ impl Depot {
         }
     }
 
+    pub fn save_flaky(&self, buf: &Vec<u8>, cmpid: u32) -> usize {
+        Self::save_input(
+            &StatusType::Crash,
+            buf,
+            &self.num_flaky,
+            cmpid,
+            &self.dirs.flaky_dir,
+        )
+    }
+
     pub fn empty(&self) -> bool {
         self.num_inputs.load(Ordering::Relaxed) == 0
     }
@@ -136,6 +165,7 @@ impl Depot {
                                 Err(p) => p.into_inner(),
                             };
                             (*branch_cov_write).push((target_cond.0,target_cond.1,v.0.base.cmpid, v.0.base.func));
+                            self.targets.solve(v.0.base.cmpid);
                             v.0.mark_as_done();
                             q.change_priority(&cond, QPriority::done());
                         } else {
@@ -173,6 +203,9 @@ impl Depot {
         } else {
             warn!("Update entry: can not find this cond");
         }
+        if cond.is_done() {
+            self.targets.solve(cond.base.cmpid);
+        }
         if cond.is_discarded() {
             q.change_priority(&cond, QPriority::done());
             let mut cfg = self.cfg.write().unwrap();
@@ -9,7 +9,10 @@ pub struct DepotDir {
     pub inputs_dir: PathBuf,
     pub hangs_dir: PathBuf,
     pub crashes_dir: PathBuf,
+    // Crashes that don't reproduce every time.
+    pub flaky_dir: PathBuf,
     pub seeds_dir: PathBuf,
+    pub inbox_dir: PathBuf,
 }
 
 impl DepotDir {
@@ -18,16 +21,23 @@ impl DepotDir {
         let inputs_dir = out_dir.join(defs::INPUTS_DIR);
         let hangs_dir = out_dir.join(defs::HANGS_DIR);
         let crashes_dir = out_dir.join(defs::CRASHES_DIR);
+        let flaky_dir = crashes_dir.join(defs::FLAKY_DIR);
+        let inbox_dir = out_dir.join(defs::INBOX_DIR);
 
-        fs::create_dir(&crashes_dir).unwrap();
-        fs::create_dir(&hangs_dir).unwrap();
-        fs::create_dir(&inputs_dir).unwrap();
+        // The directories are kept if we resume from an existing output directory.
+        for dir in &[&crashes_dir, &flaky_dir, &hangs_dir, &inputs_dir, &inbox_dir] {
+            if !dir.is_dir() {
+                fs::create_dir(dir).unwrap();
+            }
+        }
 
         Self {
             inputs_dir,
             hangs_dir,
             crashes_dir,
+            flaky_dir,
             seeds_dir,
+            inbox_dir,
         }
     }
 }
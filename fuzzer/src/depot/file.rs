@@ -9,6 +9,33 @@ pub fn get_file_name(dir: &Path, id: usize) -> PathBuf {
     dir.join(file_name)
 }
 
+// The next id to use in the directory, i.e. the largest "id:NNNNNN" + 1.
+pub fn next_file_id(dir: &Path) -> usize {
+    let mut next_id = 0;
+    if let Ok(entries) = dir.read_dir() {
+        for entry in entries.flatten() {
+            if let Ok(name) = entry.file_name().into_string() {
+                if let Some(Ok(id)) = name.strip_prefix("id:").map(|s| s.parse::<usize>()) {
+                    next_id = next_id.max(id + 1);
+                }
+            }
+        }
+    }
+    next_id
+}
+
+// The files in the directory, sorted by name.
+pub fn list_files(dir: &Path) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = fs::read_dir(dir)
+        .expect("Could not read the directory")
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file())
+        .collect();
+    files.sort();
+    files
+}
+
 pub fn read_from_file(path: &Path) -> Vec<u8> {
     let mut file;
     let mut i = 0;
@@ -3,6 +3,7 @@ mod depot_dir;
 mod dump;
 mod file;
 mod qpriority;
+mod state;
 mod sync;
 
 pub use self::{depot::Depot, file::*, sync::*};
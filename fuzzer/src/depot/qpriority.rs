@@ -6,7 +6,7 @@ const AFL_INIT_PRIORITY: u16 = 0;
 const DONE_PRIORITY: u16 = std::u16::MAX;
 const INIT_DISTANCE: u32 = std::u32::MAX;
 
-#[derive(Eq, PartialEq, Clone, Copy, Debug)]
+#[derive(Eq, PartialEq, Clone, Copy, Debug, Serialize, Deserialize)]
 pub struct QPriority(u16, u32);
 impl QPriority {
     pub fn inc(&self, op: u32) -> Self {
@@ -0,0 +1,64 @@
+use super::*;
+use crate::{cond_stmt::CondStmt, executor::StatusType};
+use std::{collections::HashMap, fs, io, path::Path};
+
+impl Depot {
+    pub fn save_queue(&self, path: &Path) -> io::Result<()> {
+        let entries: Vec<(CondStmt, QPriority)> = {
+            let q = match self.queue.lock() {
+                Ok(guard) => guard,
+                Err(poisoned) => {
+                    warn!("Mutex poisoned! Results may be incorrect. Continuing...");
+                    poisoned.into_inner()
+                },
+            };
+            q.iter().map(|(c, p)| (c.clone(), *p)).collect()
+        };
+        let f = io::BufWriter::new(fs::File::create(path)?);
+        serde_json::to_writer(f, &entries)?;
+        Ok(())
+    }
+
+    pub fn load_queue(&self, path: &Path) -> io::Result<usize> {
+        let f = io::BufReader::new(fs::File::open(path)?);
+        let entries: Vec<(CondStmt, QPriority)> = serde_json::from_reader(f)?;
+        let num = entries.len();
+        let mut q = self.queue.lock().unwrap();
+        for (cond, p) in entries {
+            q.push(cond, p);
+        }
+        Ok(num)
+    }
+
+    // Add the conditions of another instance that are not in the queue,
+    // with a copy of the inputs they belong to. Returns the number added.
+    pub fn import_queue(&self, path: &Path, inputs_dir: &Path) -> io::Result<usize> {
+        let f = io::BufReader::new(fs::File::open(path)?);
+        let entries: Vec<(CondStmt, QPriority)> = serde_json::from_reader(f)?;
+        let mut ids = HashMap::new();
+        let mut num = 0;
+        let mut q = self.queue.lock().unwrap();
+        for (mut cond, p) in entries {
+            if p.is_done() || q.get(&cond).is_some() {
+                continue;
+            }
+            let belong = cond.base.belong as usize;
+            let id = match ids.get(&belong) {
+                Some(&id) => id,
+                None => {
+                    let input = get_file_name(inputs_dir, belong);
+                    if !input.is_file() {
+                        continue;
+                    }
+                    let id = self.save(StatusType::Normal, &read_from_file(&input), cond.base.cmpid);
+                    ids.insert(belong, id);
+                    id
+                },
+            };
+            cond.base.belong = id as u32;
+            q.push(cond, p);
+            num += 1;
+        }
+        Ok(num)
+    }
+}
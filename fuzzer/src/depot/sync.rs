@@ -1,16 +1,38 @@
 use super::*;
-use crate::executor::Executor;
+use crate::{
+    dyncfg::cfg::SharedCfg,
+    executor::{Executor, StatusType},
+    resume,
+};
 use angora_common::{config, defs};
 use std::{
-    collections::HashMap,
+    collections::{hash_map::DefaultHasher, BTreeMap, HashMap, HashSet},
     fs,
-    path::Path,
+    hash::{Hash, Hasher},
+    io,
+    path::{Path, PathBuf},
     sync::{
         atomic::{AtomicBool, Ordering},
         Arc,
     },
+    time::{Duration, SystemTime},
 };
 
+// Files modified more recently may still be being written.
+const SYNC_MIN_AGE: Duration = Duration::from_secs(1);
+
+// Run one file, and keep it if it has new paths. None if it is too long.
+pub fn sync_file(executor: &mut Executor, path: &Path) -> Option<StatusType> {
+    let file_len = fs::metadata(path).expect("Could not fetch metadata.").len() as usize;
+    if file_len < config::MAX_INPUT_LEN {
+        let buf = read_from_file(path);
+        Some(executor.run_sync(&buf))
+    } else {
+        warn!("Seed discarded, too long: {:?}", path);
+        None
+    }
+}
+
 pub fn sync_depot(executor: &mut Executor, running: Arc<AtomicBool>, dir: &Path) {
     executor.local_stats.clear();
     let seed_dir = dir.read_dir().expect("read_dir call failed");
@@ -21,14 +43,7 @@ pub fn sync_depot(executor: &mut Executor, running: Arc<AtomicBool>, dir: &Path)
             }
             let path = &entry.path();
             if path.is_file() {
-                let file_len =
-                    fs::metadata(path).expect("Could not fetch metadata.").len() as usize;
-                if file_len < config::MAX_INPUT_LEN {
-                    let buf = read_from_file(path);
-                    executor.run_sync(&buf);
-                } else {
-                    warn!("Seed discarded, too long: {:?}", path);
-                }
+                sync_file(executor, path);
             }
         }
     }
@@ -36,88 +51,183 @@ pub fn sync_depot(executor: &mut Executor, running: Arc<AtomicBool>, dir: &Path)
     executor.update_log();
 }
 
-// Now we are in a sub-dir of AFL's output dir
-pub fn sync_afl(
-    executor: &mut Executor,
-    running: Arc<AtomicBool>,
-    sync_dir: &Path,
-    sync_ids: &mut HashMap<String, usize>,
-) {
-    executor.rebind_forksrv();
-    executor.local_stats.clear();
+#[derive(Default, Serialize)]
+pub struct SourceStats {
+    pub files: usize,
+    // Run, i.e. not seen before with the same content.
+    pub imported: usize,
+    pub new_paths: usize,
+}
 
+// What was synced from the other fuzzers, the foreign corpora and the inbox.
+#[derive(Default)]
+pub struct SyncState {
+    // The modification time of the files already looked at.
+    mtimes: HashMap<PathBuf, SystemTime>,
+    // The same input may be in several corpora, or be renamed.
+    hashes: HashSet<u64>,
+    pub sources: BTreeMap<String, SourceStats>,
+}
+
+impl SyncState {
+    // The modification time of the file if it changed since the last time,
+    // and is not being written.
+    fn changed(&self, path: &Path, now: SystemTime) -> Option<SystemTime> {
+        let modified = fs::metadata(path).and_then(|m| m.modified()).ok()?;
+        let settled = matches!(now.duration_since(modified), Ok(age) if age >= SYNC_MIN_AGE);
+        if settled && self.mtimes.get(path) != Some(&modified) {
+            Some(modified)
+        } else {
+            None
+        }
+    }
+
+    pub fn save(&self, path: &Path) {
+        let res = serde_json::to_string(&self.sources)
+            .map_err(io::Error::from)
+            .and_then(|s| fs::write(path, s));
+        if let Err(e) = res {
+            warn!("Could not write {:?}: {:?}", path, e);
+        }
+    }
+}
+
+fn content_hash(buf: &[u8]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    buf.hash(&mut hasher);
+    hasher.finish()
+}
+
+// The queues of the other fuzzers (AFL or ParmeSan instances) in the shared output dir,
+// we are in its sub-dir own_name.
+pub fn afl_sources(sync_dir: &Path, own_name: &str) -> Vec<(String, PathBuf)> {
+    let mut sources = vec![];
     if let Ok(entries) = sync_dir.read_dir() {
         for entry in entries {
             if let Ok(entry) = entry {
-                let entry_path = entry.path();
-                if entry_path.is_dir() {
-                    let file_name = entry.file_name().into_string();
-                    if let Ok(name) = file_name {
-                        if !name.contains(defs::ANGORA_DIR_NAME) && !name.starts_with(".") {
-                            let path = entry_path.join("queue");
-                            if path.is_dir() {
-                                sync_one_afl_dir(executor, running.clone(), &path, &name, sync_ids);
-                            }
-                        }
+                if let Ok(name) = entry.file_name().into_string() {
+                    let path = entry.path().join(defs::INPUTS_DIR);
+                    if name != own_name && !name.starts_with('.') && path.is_dir() {
+                        sources.push((name, path));
                     }
                 }
             }
         }
     }
+    sources.sort();
+    sources
+}
 
-    let n: usize = executor.local_stats.num_inputs.into();
-    info!("sync {} file from AFL.", n);
-
-    executor.update_log();
+// Import what the cfgs of the other ParmeSan instances learned, from their saved state.
+pub fn sync_cfgs(cfg: &SharedCfg, sync_dir: &Path, own_name: &str, state: &mut SyncState) {
+    let now = SystemTime::now();
+    for (name, _) in afl_sources(sync_dir, own_name) {
+        let path = sync_dir.join(&name).join(defs::STATE_DIR).join(defs::CFG_STATE_FILE);
+        let modified = match state.changed(&path, now) {
+            Some(t) => t,
+            None => continue,
+        };
+        match resume::read_cfg_state(&path) {
+            Ok(cfg_state) => {
+                debug!("sync {} cfg edges from {}.", cfg_state.edges.len(), name);
+                cfg.write().unwrap().load_state(cfg_state);
+            },
+            Err(e) => warn!("Could not import the cfg of {}: {:?}", name, e),
+        }
+        state.mtimes.insert(path, modified);
+    }
 }
 
-fn get_afl_id(f: &fs::DirEntry) -> Option<usize> {
-    let file_name = f.file_name().into_string();
-    if let Ok(name) = file_name {
-        if name.len() >= 9 {
-            let id_str = &name[3..9];
-            if let Ok(id) = id_str.parse::<usize>() {
-                return Some(id);
-            }
+// Import the saved condition queues of the other ParmeSan instances.
+pub fn sync_cond_queues(depot: &Depot, sync_dir: &Path, own_name: &str, state: &mut SyncState) {
+    let now = SystemTime::now();
+    for (name, inputs_dir) in afl_sources(sync_dir, own_name) {
+        let path = sync_dir.join(&name).join(defs::STATE_DIR).join(defs::QUEUE_STATE_FILE);
+        let modified = match state.changed(&path, now) {
+            Some(t) => t,
+            None => continue,
+        };
+        match depot.import_queue(&path, &inputs_dir) {
+            Ok(num) => info!("sync {} conditions from {}.", num, name),
+            Err(e) => warn!("Could not import the conditions of {}: {:?}", name, e),
         }
+        state.mtimes.insert(path, modified);
     }
-    None
 }
 
-fn sync_one_afl_dir(
+// Run the files of the sources (name, dir) that are new or changed since the last time,
+// whatever their names are. Returns the number of inputs run.
+pub fn sync_sources(
     executor: &mut Executor,
     running: Arc<AtomicBool>,
-    sync_dir: &Path,
-    sync_name: &str,
-    sync_ids: &mut HashMap<String, usize>,
-) {
-    let min_id = *sync_ids.get(sync_name).unwrap_or(&0);
-    let mut max_id = min_id;
-    let seed_dir = sync_dir
-        .read_dir()
-        .expect("read_dir call failed while syncing afl ..");
-    for entry in seed_dir {
-        if let Ok(entry) = entry {
-            if !running.load(Ordering::SeqCst) {
-                break;
-            }
-            let path = &entry.path();
-            if path.is_file() {
-                if let Some(id) = get_afl_id(&entry) {
-                    if id >= min_id {
-                        let file_len = fs::metadata(path).unwrap().len() as usize;
-                        if file_len < config::MAX_INPUT_LEN {
-                            let buf = read_from_file(path);
-                            executor.run_sync(&buf);
-                        }
-                        if id > max_id {
-                            max_id = id;
-                        }
-                    }
-                }
+    sources: &[(String, PathBuf)],
+    state: &mut SyncState,
+) -> usize {
+    let now = SystemTime::now();
+    let mut new_files = vec![];
+    for (name, dir) in sources {
+        let files = list_files(dir);
+        state.sources.entry(name.clone()).or_default().files = files.len();
+        for path in files {
+            if let Some(modified) = state.changed(&path, now) {
+                new_files.push((name, path, modified));
             }
         }
     }
+    if new_files.is_empty() {
+        return 0;
+    }
+
+    executor.rebind_forksrv();
+    executor.local_stats.clear();
+    let mut num_run = 0;
+    for (name, path, modified) in new_files {
+        if !running.load(Ordering::SeqCst) {
+            break;
+        }
+        state.mtimes.insert(path.clone(), modified);
+        let buf = read_from_file(&path);
+        if buf.len() >= config::MAX_INPUT_LEN {
+            warn!("Seed discarded, too long: {:?}", path);
+            continue;
+        }
+        if !state.hashes.insert(content_hash(&buf)) {
+            continue;
+        }
+        executor.run_sync(&buf);
+        num_run += 1;
+        let stats = state.sources.get_mut(name).unwrap();
+        stats.imported += 1;
+        if executor.has_new_path {
+            stats.new_paths += 1;
+        }
+    }
 
-    sync_ids.insert(sync_name.to_string(), max_id + 1);
+    info!("sync {} file from other fuzzers, corpora and the inbox.", num_run);
+    for (name, stats) in &state.sources {
+        debug!(
+            "sync {}: {} files, {} imported, {} with new paths",
+            name, stats.files, stats.imported, stats.new_paths
+        );
+    }
+    executor.update_log();
+    num_run
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn afl_sources_skip_own_dir() {
+        let dir = std::env::temp_dir().join(format!("parmesan_sync_{}", std::process::id()));
+        for name in &["main", "sec1", "fuzzer02", ".hidden"] {
+            fs::create_dir_all(dir.join(name).join(defs::INPUTS_DIR)).unwrap();
+        }
+        fs::create_dir_all(dir.join("no_queue")).unwrap();
+        let sources = afl_sources(&dir, "sec1");
+        fs::remove_dir_all(&dir).unwrap();
+        let names: Vec<&str> = sources.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(names, vec!["fuzzer02", "main"]);
+    }
 }
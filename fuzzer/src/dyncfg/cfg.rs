@@ -1,23 +1,35 @@
 use std::f64;
 use math::mean;
 use petgraph::graphmap::DiGraphMap;
-use std::collections::{HashSet, HashMap};
-use petgraph::visit::{Reversed, Bfs, Dfs};
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashSet, HashMap};
+use std::sync::{Arc, RwLock};
+use petgraph::visit::Dfs;
 use petgraph::{Incoming, Outgoing};
-use angora_common::tag::TagSeg;
-use super::fparse::CfgFile;
+use angora_common::{config::CFG_PROPAGATE_LIMIT, tag::TagSeg};
+use super::{
+    export::{CfgExport, ExportEdge, ExportNode},
+    fparse::CfgFile,
+    score::ScoreStrategy,
+};
 
 pub type CmpId = u32;
 pub type CallSiteId = u32;
 pub type Edge = (CmpId, CmpId);
 pub type Score = u32;
 pub type FixedBytes = Vec<(usize, u8)>;
+// The one CFG of the fuzzer: the edges learned by tracking are seen by every thread.
+pub type SharedCfg = Arc<RwLock<ControlFlowGraph>>;
+// The cmps with a path to a target, behind their own lock: the fuzzing threads
+// look them up after each run, while the cfg lock may be held by track().
+pub type SharedReaching = Arc<RwLock<HashSet<CmpId>>>;
 
 const TARGET_SCORE: Score = 0;
 const UNDEF_SCORE: Score = std::u32::MAX;
+// Coverage adds up around the loops of the graph, it stops here.
+const MAX_SCORE: Score = UNDEF_SCORE - 1;
 
 
-#[derive(Clone)]
 pub struct ControlFlowGraph {
     graph: DiGraphMap<CmpId, Score>,
     targets: HashSet<CmpId>,
@@ -27,9 +39,26 @@ pub struct ControlFlowGraph {
     callsite_dominators: HashMap<CallSiteId, HashSet<CmpId>>,
     dominator_cmps: HashSet<CmpId>,
     magic_bytes: HashMap<Edge, FixedBytes>,
+    strategy: ScoreStrategy,
+    // How many times each edge was seen, for ScoreStrategy::Weighted.
+    edge_hits: HashMap<Edge, u32>,
+    // The cmps with a path to a target that is not solved yet.
+    reaching: SharedReaching,
+    // The cmps whose score may be stale, left over by the last propagation.
+    dirty: HashSet<CmpId>,
 }
 
-
+// What we learned at runtime, saved to resume a campaign.
+// The static part (targets, dominators) is read from the cfg file again.
+#[derive(Serialize, Deserialize, Default, Debug)]
+pub struct CfgState {
+    pub solved_targets: Vec<CmpId>,
+    pub edges: Vec<Edge>,
+    pub indirect_edges: Vec<(CallSiteId, Edge)>,
+    pub magic_bytes: Vec<(Edge, FixedBytes)>,
+    #[serde(default)]
+    pub edge_hits: Vec<(Edge, u32)>,
+}
 
 // A CFG of branches (CMPs)
 impl ControlFlowGraph {
@@ -39,6 +68,7 @@ impl ControlFlowGraph {
         for s in data.callsite_dominators.values() {
             dominator_cmps.extend(s)
         }
+        let reaching = Arc::new(RwLock::new(data.targets.clone()));
         let mut result = ControlFlowGraph {
             graph: DiGraphMap::new(),
             targets: data.targets,
@@ -48,10 +78,24 @@ impl ControlFlowGraph {
             callsite_dominators: data.callsite_dominators,
             dominator_cmps,
             magic_bytes: HashMap::new(),
+            strategy: ScoreStrategy::default(),
+            edge_hits: HashMap::new(),
+            reaching,
+            dirty: HashSet::new(),
         };
 
-        for e in data.edges {
-            result.add_edge(e);
+        let edges: Vec<Edge> = data.edges.into_iter().collect();
+        for &e in &edges {
+            result.insert_edge(e);
+        }
+        // Not shared yet, so the whole graph at once. A pass updates each cmp once and
+        // the loops need a few more, but at most one pass per cmp.
+        let all: HashSet<CmpId> = result.graph.nodes().collect();
+        result.reset_scores(all);
+        let mut passes = result.graph.node_count();
+        while !result.dirty.is_empty() && passes > 0 {
+            result.propagate_scores(usize::MAX);
+            passes -= 1;
         }
 
         info!("INIT CFG: dominators: {:?}", result.dominator_cmps);
@@ -59,12 +103,158 @@ impl ControlFlowGraph {
         result
     }
 
+    pub fn get_state(&self) -> CfgState {
+        let mut indirect_edges = vec![];
+        for (cs, edges) in &self.callsite_edges {
+            for e in edges {
+                indirect_edges.push((*cs, *e));
+            }
+        }
+        CfgState {
+            solved_targets: self.solved_targets.iter().cloned().collect(),
+            edges: self.graph.all_edges().map(|(a, b, _)| (a, b)).collect(),
+            indirect_edges,
+            magic_bytes: self.magic_bytes.iter().map(|(e, f)| (*e, f.clone())).collect(),
+            edge_hits: self.edge_hits.iter().map(|(e, h)| (*e, *h)).collect(),
+        }
+    }
 
+    // The graph with the current scores, see export.rs.
+    pub fn export(&self) -> CfgExport {
+        let mut ids: Vec<CmpId> = self.graph.nodes().collect();
+        ids.extend(self.targets.iter().chain(self.solved_targets.iter()).filter(|t| !self.graph.contains_node(**t)));
+        ids.sort();
+        let defined = |s: Score| if s == UNDEF_SCORE { None } else { Some(s) };
+        let nodes = ids
+            .into_iter()
+            .map(|id| ExportNode {
+                id,
+                score: defined(self._score_for_cmp(id)),
+                target: self.targets.contains(&id),
+                solved: self.solved_targets.contains(&id),
+            })
+            .collect();
+
+        // The callsites of each edge, looked up once instead of per edge.
+        let mut edge_callsites: HashMap<Edge, Vec<CallSiteId>> = HashMap::new();
+        for (cs, edges) in &self.callsite_edges {
+            for edge in edges {
+                edge_callsites.entry(*edge).or_insert(vec![]).push(*cs);
+            }
+        }
+
+        let mut edges: Vec<ExportEdge> = self
+            .graph
+            .all_edges()
+            .map(|(src, dst, score)| {
+                let edge = (src, dst);
+                let mut callsites = edge_callsites.remove(&edge).unwrap_or_default();
+                callsites.sort();
+                ExportEdge {
+                    src,
+                    dst,
+                    score: defined(*score),
+                    hits: self.edge_hits.get(&edge).cloned().unwrap_or(0),
+                    callsites,
+                    magic_bytes: self.get_magic_bytes(edge),
+                }
+            })
+            .collect();
+        edges.sort_by_key(|e| (e.src, e.dst));
+
+        CfgExport {
+            strategy: self.strategy,
+            nodes,
+            edges,
+        }
+    }
+
+    pub fn load_state(&mut self, state: CfgState) {
+        for t in state.solved_targets {
+            self.remove_target(t);
+        }
+        // Hits are only counted by the runs, so take the ones of the state as they are,
+        // e.g. a cfg imported again at each sync does not count its edges again.
+        for (e, hits) in state.edge_hits {
+            let old = self.edge_hits.entry(e).or_insert(0);
+            if hits > *old {
+                *old = hits;
+                if self.strategy == ScoreStrategy::Weighted {
+                    self.dirty.insert(e.0);
+                }
+            }
+        }
+        self.add_edges(&state.edges);
+        for (cs, e) in state.indirect_edges {
+            self.set_edge_indirect(e, cs);
+        }
+        for (e, fixed) in state.magic_bytes {
+            self.magic_bytes.insert(e, fixed);
+        }
+    }
+
+    // Computes the scores again from the targets, over the next batches.
+    pub fn set_score_strategy(&mut self, strategy: ScoreStrategy) {
+        self.strategy = strategy;
+        let all: HashSet<CmpId> = self.graph.nodes().collect();
+        self.reset_scores(all);
+        self.propagate_scores(CFG_PROPAGATE_LIMIT);
+    }
+
+    pub fn get_score_strategy(&self) -> ScoreStrategy {
+        self.strategy
+    }
+
+    // A run with one edge.
     pub fn add_edge(&mut self, edge: Edge) -> bool {
-        let result = !self.has_edge(edge);
-        self.handle_new_edge(edge);
-        debug!("Added CFG edge {:?} {}", edge, self.targets.contains(&edge.1));
-        result
+        self.add_run_edges(&[edge]) > 0
+    }
+
+    // The edges of one tracked run: each one is counted for ScoreStrategy::Weighted.
+    pub fn add_run_edges(&mut self, edges: &[Edge]) -> usize {
+        for &edge in edges {
+            *self.edge_hits.entry(edge).or_insert(0) += 1;
+            if self.strategy == ScoreStrategy::Weighted {
+                self.dirty.insert(edge.0);
+            }
+        }
+        self.add_edges(edges)
+    }
+
+    // Add the edges, then update the scores once for all of them.
+    // Returns the number of new edges.
+    pub fn add_edges(&mut self, edges: &[Edge]) -> usize {
+        let mut num_new = 0;
+        for &edge in edges {
+            if self.insert_edge(edge) {
+                num_new += 1;
+            }
+        }
+        self.propagate_scores(CFG_PROPAGATE_LIMIT);
+        num_new
+    }
+
+    // Insert the edge with the current score of its destination, and mark the source
+    // if its score may change.
+    fn insert_edge(&mut self, edge: Edge) -> bool {
+        let (src, dst) = edge;
+        let is_new = !self.has_edge(edge);
+        let new_reaching = {
+            let reaching = self.reaching.read().unwrap();
+            reaching.contains(&dst) && !reaching.contains(&src)
+        };
+        if new_reaching {
+            self.mark_reaching(src);
+        }
+        if is_new {
+            let dst_score = self._score_for_cmp(dst);
+            self.graph.add_edge(src, dst, dst_score);
+        }
+        if is_new {
+            self.dirty.insert(src);
+        }
+        debug!("Added CFG edge {:?} {}", edge, self.targets.contains(&dst));
+        is_new
     }
 
     pub fn set_edge_indirect(&mut self, edge: Edge, callsite: CallSiteId) {
@@ -111,61 +301,108 @@ impl ControlFlowGraph {
 
     pub fn remove_target(&mut self, cmp: CmpId) {
         if self.targets.remove(&cmp) {
-            self.propagate_score(cmp);
             self.solved_targets.insert(cmp);
+            // Only the cmps with a path to it change.
+            let mut affected = HashSet::new();
+            self.add_reaching(cmp, &mut affected);
+            self.reset_scores(affected);
+            self.propagate_scores(CFG_PROPAGATE_LIMIT);
+            // The set can only shrink here, so compute it again and swap it in.
+            let mut reaching = HashSet::new();
+            for &t in &self.targets {
+                self.add_reaching(t, &mut reaching);
+            }
+            *self.reaching.write().unwrap() = reaching;
         }
     }
 
-    pub fn is_target(&self, cmp: CmpId) -> bool {
-        self.targets.contains(&cmp) || self.solved_targets.contains(&cmp)
+    fn mark_reaching(&self, cmp: CmpId) {
+        let mut reaching = self.reaching.write().unwrap();
+        self.add_reaching(cmp, &mut reaching);
     }
 
-
-    fn handle_new_edge(&mut self, edge: Edge) {
-        let (src, dst) = edge;
-
-        // 1) Get score for dst
-        let dst_score = self._score_for_cmp(dst);
-        
-        // 2) if src_score changed
-        let old_src_score = self._score_for_cmp(src);
-
-        // Insert edge in graph
-        self.graph.add_edge(src, dst, dst_score);
-
-        let new_src_score = self._score_for_cmp(src);
-
-        if old_src_score == new_src_score {
-            // No change in score
-            return;
+    // Add cmp and the cmps with a path to it, up to the ones already there.
+    fn add_reaching(&self, cmp: CmpId, reaching: &mut HashSet<CmpId>) {
+        let mut stack = vec![cmp];
+        while let Some(c) = stack.pop() {
+            if reaching.insert(c) && self.graph.contains_node(c) {
+                stack.extend(self.graph.neighbors_directed(c, Incoming));
+            }
         }
+    }
 
-        self.graph.add_edge(src, dst, dst_score);
-        self.propagate_score(src);
+    // All the targets of the cfg file, solved or not.
+    pub fn get_targets(&self) -> Vec<CmpId> {
+        let mut result: Vec<CmpId> = self.targets.union(&self.solved_targets).cloned().collect();
+        result.sort();
+        result
     }
 
+    pub fn get_solved_targets(&self) -> Vec<CmpId> {
+        let mut result: Vec<CmpId> = self.solved_targets.iter().cloned().collect();
+        result.sort();
+        result
+    }
 
-    fn propagate_score(&mut self, cmp: CmpId) {
+    pub fn is_target(&self, cmp: CmpId) -> bool {
+        self.targets.contains(&cmp) || self.solved_targets.contains(&cmp)
+    }
 
-        let rev_graph = Reversed(&self.graph);
-        let mut visitor = Bfs::new(rev_graph, cmp);
 
-        while let Some(visited) = visitor.next(&self.graph) {
-            let new_score = self._score_for_cmp(visited);
-            let mut predecessors = vec![];
-            {
-                let neighbors = self.graph.neighbors_directed(visited, Incoming);
-                for n in neighbors {
-                    predecessors.push(n);
-                }
+    // Update the scores from the dirty cmps to their predecessors, in the order of
+    // their scores like Dijkstra, so that with ScoreStrategy::Min the scores are the
+    // shortest distances to the targets. It stops at the cmps whose score didn't change.
+    // Each cmp is updated at most once and at most `limit` cmps are updated, the others
+    // stay dirty for the next call, so the time holding the lock is bounded.
+    fn propagate_scores(&mut self, limit: usize) {
+        let dirty: Vec<CmpId> = self.dirty.drain().collect();
+        let mut heap: BinaryHeap<Reverse<(Score, CmpId)>> =
+            dirty.into_iter().map(|c| Reverse((self._score_for_cmp(c), c))).collect();
+        let mut done = HashSet::new();
+        while let Some(Reverse((_, cmp))) = heap.pop() {
+            if done.contains(&cmp) {
+                continue;
+            }
+            if done.len() >= limit {
+                self.dirty.insert(cmp);
+                continue;
             }
+            done.insert(cmp);
+
+            let score = self._score_for_cmp(cmp);
+            let predecessors: Vec<CmpId> = self.graph.neighbors_directed(cmp, Incoming).collect();
             for p in predecessors {
-                self.graph.add_edge(p, visited, new_score);
+                if self.graph.edge_weight(p, cmp) == Some(&score) {
+                    continue;
+                }
+                self.graph.add_edge(p, cmp, score);
+                if done.contains(&p) {
+                    // Its score changed after its update, e.g. in a loop.
+                    self.dirty.insert(p);
+                } else {
+                    heap.push(Reverse((self._score_for_cmp(p), p)));
+                }
             }
         }
-        
+        if !self.dirty.is_empty() {
+            debug!("CFG propagation: {} cmps left for the next batch", self.dirty.len());
+        }
+    }
+
+    // Forget the scores of cmps, e.g. when a target is solved. Like the sources of new
+    // edges, they are computed again by the next batches of propagate_scores.
+    fn reset_scores(&mut self, cmps: HashSet<CmpId>) {
+        let edges: Vec<Edge> = self
+            .graph
+            .all_edges()
+            .filter(|(_, b, _)| cmps.contains(b))
+            .map(|(a, b, _)| (a, b))
+            .collect();
+        for (a, b) in edges {
+            self.graph.add_edge(a, b, UNDEF_SCORE);
+        }
+        self.dirty.extend(cmps);
     }
-    
 
     pub fn has_edge(&self, edge: Edge) -> bool {
         let (a,b) = edge;
@@ -179,10 +416,14 @@ impl ControlFlowGraph {
         false
     }
 
-    fn aggregate_score(ovals: Vec<Score>) -> Score {
-        //Self::score_greedy(ovals)
-        //Self::score_coverage(ovals)
-        Self::score_harmonic_mean(ovals)
+    // The scores of the successors, with how many times the edge to them was seen.
+    fn aggregate_score(&self, ovals: Vec<(Score, u32)>) -> Score {
+        match self.strategy {
+            ScoreStrategy::Harmonic => Self::score_harmonic_mean(ovals.into_iter().map(|(s, _)| s).collect()),
+            ScoreStrategy::Min => Self::score_greedy(ovals.into_iter().map(|(s, _)| s).collect()),
+            ScoreStrategy::Coverage => Self::score_coverage(ovals.into_iter().map(|(s, _)| s).collect()),
+            ScoreStrategy::Weighted => Self::score_weighted_harmonic_mean(ovals),
+        }
     }
 
     fn score_harmonic_mean(ovals: Vec<Score>) -> Score {
@@ -191,10 +432,28 @@ impl ControlFlowGraph {
         }
         let vals = ovals.into_iter().filter(|v| *v != UNDEF_SCORE);
         let fvals : Vec<f64> = vals.into_iter().map(|x| x as f64).collect();
+        // No successor with a path to a target.
+        if fvals.is_empty() {
+            return UNDEF_SCORE;
+        }
         return mean::harmonic(fvals.as_slice()) as u32 + 1;
     }
 
-    #[allow(dead_code)]
+    fn score_weighted_harmonic_mean(ovals: Vec<(Score, u32)>) -> Score {
+        let vals: Vec<(f64, f64)> = ovals
+            .into_iter()
+            .filter(|&(s, _)| s != UNDEF_SCORE)
+            .map(|(s, w)| (s as f64, w.max(1) as f64))
+            .collect();
+        if vals.is_empty() {
+            return UNDEF_SCORE;
+        }
+        let sum_w: f64 = vals.iter().map(|&(_, w)| w).sum();
+        // A target (score 0) makes it 0, like the harmonic mean.
+        let sum_w_s: f64 = vals.iter().map(|&(s, w)| w / s).sum();
+        (sum_w / sum_w_s) as u32 + 1
+    }
+
     fn score_greedy(ovals: Vec<Score>) -> Score {
         let vals = ovals.into_iter().filter(|v| *v != UNDEF_SCORE);
         if let Some(v) = vals.min() {
@@ -204,24 +463,38 @@ impl ControlFlowGraph {
         }
     }
 
-    #[allow(dead_code)]
     fn score_coverage(ovals: Vec<Score>) -> Score {
         if ovals.len() == 0 {
             return UNDEF_SCORE;
         }
-        let vals = ovals.into_iter().filter(|v| *v != UNDEF_SCORE);
+        let vals: Vec<Score> = ovals.into_iter().filter(|v| *v != UNDEF_SCORE).collect();
+        if vals.is_empty() {
+            return UNDEF_SCORE;
+        }
         let vals_norm = vals.into_iter().map(|v| if v == TARGET_SCORE {1} else {v});
-        vals_norm.sum()
+        vals_norm.fold(0, |sum: Score, v| sum.saturating_add(v)).min(MAX_SCORE)
+    }
+
+    // Whether cmp has a path to a target that is not solved yet.
+    pub fn has_path_to_target(&self, cmp: CmpId) -> bool {
+        self.reaching.read().unwrap().contains(&cmp)
     }
 
-    pub fn has_path_to_target(&self, target: CmpId) -> bool {
-        let mut dfs = Dfs::new(&self.graph, target);
+    pub fn get_reaching(&self) -> SharedReaching {
+        self.reaching.clone()
+    }
+
+    // The targets not solved yet with a path from cmp.
+    pub fn reachable_targets(&self, cmp: CmpId) -> Vec<CmpId> {
+        let mut result = vec![];
+        let mut dfs = Dfs::new(&self.graph, cmp);
         while let Some(visited) = dfs.next(&self.graph) {
             if self.targets.contains(&visited) {
-                return true;
+                result.push(visited);
             }
         }
-        false
+        result.sort();
+        result
     }
 
     pub fn score_for_cmp(&self, cmp: CmpId) -> Score {
@@ -260,10 +533,10 @@ impl ControlFlowGraph {
             }
             debug!("Counting edge: {:?}", edge);
             if let Some(s) = self.graph.edge_weight(cmp, n) {
-                scores.push(*s);
+                scores.push((*s, self.edge_hits.get(&edge).cloned().unwrap_or(1)));
             }
         }
-        return Self::aggregate_score(scores);
+        return self.aggregate_score(scores);
     }
 
     fn _should_count_edge(&self, edge: Edge, inp: &Vec<u8>) -> bool {
@@ -294,11 +567,18 @@ impl ControlFlowGraph {
 mod tests {
     use super::*;
 
+    fn new_cfg(targets: Vec<CmpId>) -> ControlFlowGraph {
+        ControlFlowGraph::new(CfgFile {
+            targets: targets.into_iter().collect(),
+            edges: HashSet::new(),
+            callsite_dominators: HashMap::new(),
+        })
+    }
+
     #[test]
     fn cfg_basic() {
         // Create CFG
-        let targets = HashSet::new();
-        let mut cfg = ControlFlowGraph::new(targets);
+        let mut cfg = new_cfg(vec![]);
         let edges = vec![(10,20), (20,30), (10,40), (40,50), (20,30)];
 
        for e in edges.clone() {
@@ -306,6 +586,190 @@ mod tests {
         }
 
     }
-    
+
+    #[test]
+    fn cfg_state_roundtrip() {
+        let mut cfg = new_cfg(vec![30, 50]);
+        for e in vec![(10,20), (20,30), (10,40), (40,50)] {
+            cfg.add_edge(e);
+        }
+        cfg.set_edge_indirect((10,40), 7);
+        cfg.set_magic_bytes((10,40), &vec![1, 2, 3], &vec![TagSeg { sign: false, begin: 1, end: 2 }]);
+        cfg.remove_target(50);
+
+        let state = serde_json::to_string(&cfg.get_state()).unwrap();
+        let mut loaded = new_cfg(vec![30, 50]);
+        loaded.load_state(serde_json::from_str(&state).unwrap());
+
+        assert!(loaded.has_edge((40,50)));
+        assert!(!loaded.has_path_to_target(40));
+        assert!(loaded.has_path_to_target(10));
+        assert!(loaded.is_target(50));
+        assert_eq!(loaded.get_magic_bytes((10,40)), vec![(1, 2)]);
+        assert_eq!(loaded.score_for_cmp(10), cfg.score_for_cmp(10));
+    }
+
+    #[test]
+    fn reachable_targets() {
+        let mut cfg = new_cfg(vec![30, 50]);
+        for e in vec![(10,20), (20,30), (10,40), (40,50)] {
+            cfg.add_edge(e);
+        }
+        assert_eq!(cfg.reachable_targets(10), vec![30, 50]);
+        assert_eq!(cfg.reachable_targets(40), vec![50]);
+        assert_eq!(cfg.reachable_targets(30), vec![30]);
+        assert!(cfg.reachable_targets(60).is_empty());
+    }
+
+    #[test]
+    fn reaching_cache() {
+        let mut cfg = new_cfg(vec![30, 50]);
+        for e in vec![(10,20), (40,50), (20,30), (60,10), (10,40)] {
+            cfg.add_edge(e);
+        }
+        for cmp in vec![10, 20, 30, 40, 50, 60] {
+            assert!(cfg.has_path_to_target(cmp));
+        }
+        assert!(!cfg.has_path_to_target(70));
+        cfg.remove_target(30);
+        assert!(!cfg.has_path_to_target(20));
+        assert!(cfg.has_path_to_target(60));
+        cfg.remove_target(50);
+        assert!(!cfg.has_path_to_target(60));
+        assert!(!cfg.has_path_to_target(50));
+    }
+
+    #[test]
+    fn score_strategies() {
+        let mut cfg = new_cfg(vec![23, 31]);
+        for e in vec![(22,23), (21,22), (20,21), (10,20), (30,31), (10,30)] {
+            cfg.add_edge(e);
+        }
+        assert_eq!(cfg.score_for_cmp(20), 3);
+        assert_eq!(cfg.score_for_cmp(10), 2);
+        cfg.set_score_strategy(ScoreStrategy::Min);
+        assert_eq!(cfg.score_for_cmp(10), 2);
+        cfg.set_score_strategy(ScoreStrategy::Coverage);
+        assert_eq!(cfg.score_for_cmp(10), 2);
+        // The far successor is seen much more often.
+        cfg.set_score_strategy(ScoreStrategy::Weighted);
+        for _ in 0..9 {
+            cfg.add_edge((10, 20));
+        }
+        assert_eq!(cfg.score_for_cmp(10), 3);
+    }
+
+    #[test]
+    fn coverage_loop() {
+        let mut cfg = new_cfg(vec![98, 99]);
+        cfg.set_score_strategy(ScoreStrategy::Coverage);
+        cfg.add_edges(&[(1,2), (1,3), (2,1), (3,1), (2,99), (3,98)]);
+        // Each time around the loop adds the score of 1 to itself.
+        for _ in 0..64 {
+            cfg.add_edges(&[(2,1), (3,1)]);
+        }
+        cfg.remove_target(99);
+        for cmp in [1, 2, 3] {
+            assert!(cfg.has_score(cmp));
+        }
+    }
+
+    #[test]
+    fn batched_edges() {
+        let edges = vec![(10,20), (20,30), (30,40), (10,50), (50,40), (60,10), (40,60)];
+        let mut one_by_one = new_cfg(vec![40]);
+        one_by_one.set_score_strategy(ScoreStrategy::Min);
+        for &e in &edges {
+            one_by_one.add_edge(e);
+        }
+        let mut batched = new_cfg(vec![40]);
+        batched.set_score_strategy(ScoreStrategy::Min);
+        assert_eq!(batched.add_edges(&edges), edges.len());
+        for cmp in vec![10, 20, 30, 50, 60] {
+            assert_eq!(batched.score_for_cmp(cmp), one_by_one.score_for_cmp(cmp));
+        }
+        assert_eq!(batched.score_for_cmp(60), 3);
+        assert_eq!(batched.score_for_cmp(10), 2);
+
+        // A shortcut only updates the cmps before it.
+        batched.add_edge((60,40));
+        assert_eq!(batched.score_for_cmp(60), 1);
+        assert_eq!(batched.score_for_cmp(10), 2);
+        batched.remove_target(40);
+        assert!(!batched.has_score(10));
+    }
+
+    #[test]
+    fn recompute_loops() {
+        let edges = vec![(10,20), (20,30), (30,10), (30,40), (10,50), (50,60)];
+        let mut cfg = new_cfg(vec![40, 60]);
+        cfg.set_score_strategy(ScoreStrategy::Min);
+        cfg.add_edges(&edges);
+        assert_eq!(cfg.score_for_cmp(10), 2);
+        assert_eq!(cfg.score_for_cmp(20), 2);
+
+        // Only 40 is left: 10 now goes around the loop. The next batches finish the update.
+        cfg.remove_target(60);
+        while !cfg.dirty.is_empty() {
+            cfg.add_edges(&[]);
+        }
+        let mut fresh = new_cfg(vec![40]);
+        fresh.set_score_strategy(ScoreStrategy::Min);
+        for &e in &edges {
+            fresh.add_edge(e);
+        }
+        for cmp in vec![10, 20, 30] {
+            assert_eq!(cfg.score_for_cmp(cmp), fresh.score_for_cmp(cmp));
+        }
+        assert_eq!(cfg.score_for_cmp(10), 3);
+    }
+
+    #[test]
+    fn edge_hits_state() {
+        let mut cfg = new_cfg(vec![30]);
+        cfg.add_run_edges(&[(10,20), (20,30)]);
+        cfg.add_run_edges(&[(10,20)]);
+        let state = serde_json::to_string(&cfg.get_state()).unwrap();
+
+        // e.g. resumed, then the same cfg imported again at the next sync.
+        let mut loaded = new_cfg(vec![30]);
+        loaded.load_state(serde_json::from_str(&state).unwrap());
+        loaded.load_state(serde_json::from_str(&state).unwrap());
+        assert_eq!(loaded.edge_hits.get(&(10,20)), Some(&2));
+        assert_eq!(loaded.edge_hits.get(&(20,30)), Some(&1));
+        loaded.add_run_edges(&[(10,20)]);
+        assert_eq!(loaded.edge_hits.get(&(10,20)), Some(&3));
+    }
+
+    #[test]
+    fn export_roundtrip() {
+        let mut cfg = new_cfg(vec![30, 50, 70]);
+        cfg.add_run_edges(&[(10,20), (20,30), (10,40), (40,50)]);
+        cfg.set_edge_indirect((10,40), 7);
+        cfg.set_magic_bytes((10,40), &vec![1, 2, 3], &vec![TagSeg { sign: false, begin: 1, end: 2 }]);
+        cfg.remove_target(50);
+
+        let export = cfg.export();
+        let ids: Vec<CmpId> = export.nodes.iter().map(|n| n.id).collect();
+        assert_eq!(ids, vec![10, 20, 30, 40, 50, 70]);
+        assert_eq!(export.nodes[2], ExportNode { id: 30, score: Some(0), target: true, solved: false });
+        assert_eq!(export.nodes[3].score, None);
+        assert!(export.nodes[4].solved);
+        assert_eq!(export.edges[1].callsites, vec![7]);
+        assert_eq!(export.edges[1].magic_bytes, vec![(1, 2)]);
+        assert!(export.to_dot().contains("10 -> 40 [label=\"hits 1\\ncallsites [7]"));
+
+        let json = serde_json::to_string(&export).unwrap();
+        let loaded: CfgExport = serde_json::from_str(&json).unwrap();
+        let mut next = ControlFlowGraph::new(CfgFile {
+            targets: vec![30, 50].into_iter().collect(),
+            edges: loaded.cfg_edges(),
+            callsite_dominators: HashMap::new(),
+        });
+        assert!(next.has_edge((40,50)));
+        assert_eq!(next.score_for_cmp(10), 2);
+        next.remove_target(50);
+        assert_eq!(next.score_for_cmp(10), cfg.score_for_cmp(10));
+    }
 }
 
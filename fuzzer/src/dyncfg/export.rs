@@ -0,0 +1,148 @@
+// Export what the cfg learned, to look at it (DOT, GraphML) or to start the next run with
+// its edges (JSON, see read_export_edges).
+use super::{
+    cfg::{CallSiteId, CmpId, Edge, FixedBytes, Score},
+    score::ScoreStrategy,
+};
+use angora_common::defs;
+use std::{
+    collections::HashSet,
+    fmt::Write as FmtWrite,
+    fs, io,
+    path::Path,
+};
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct ExportNode {
+    pub id: CmpId,
+    // None if the cmp has no path to a target.
+    pub score: Option<Score>,
+    pub target: bool,
+    pub solved: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct ExportEdge {
+    pub src: CmpId,
+    pub dst: CmpId,
+    // The score of dst when the score of src was computed.
+    pub score: Option<Score>,
+    pub hits: u32,
+    // Not empty for indirect-call edges.
+    pub callsites: Vec<CallSiteId>,
+    pub magic_bytes: FixedBytes,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct CfgExport {
+    pub strategy: ScoreStrategy,
+    pub nodes: Vec<ExportNode>,
+    pub edges: Vec<ExportEdge>,
+}
+
+fn format_score(score: Option<Score>) -> String {
+    match score {
+        Some(s) => s.to_string(),
+        None => "-".to_string(),
+    }
+}
+
+impl CfgExport {
+    // The edges of a CfgFile.
+    pub fn cfg_edges(&self) -> HashSet<Edge> {
+        self.edges.iter().map(|e| (e.src, e.dst)).collect()
+    }
+
+    // Targets are double circles, red if not solved yet, and indirect-call edges are dashed.
+    pub fn to_dot(&self) -> String {
+        let mut out = String::new();
+        writeln!(out, "digraph cfg {{").unwrap();
+        writeln!(out, "  // distance: {:?}", self.strategy).unwrap();
+        for n in &self.nodes {
+            let mut attrs = format!("label=\"{}\\n{}\"", n.id, format_score(n.score));
+            if n.target {
+                attrs.push_str(", shape=doublecircle, color=red");
+            } else if n.solved {
+                attrs.push_str(", shape=doublecircle, color=green");
+            }
+            writeln!(out, "  {} [{}];", n.id, attrs).unwrap();
+        }
+        for e in &self.edges {
+            let mut label = format!("hits {}", e.hits);
+            if !e.callsites.is_empty() {
+                write!(label, "\\ncallsites {:?}", e.callsites).unwrap();
+            }
+            if !e.magic_bytes.is_empty() {
+                write!(label, "\\nmagic {:?}", e.magic_bytes).unwrap();
+            }
+            let style = if e.callsites.is_empty() { "solid" } else { "dashed" };
+            writeln!(out, "  {} -> {} [label=\"{}\", style={}];", e.src, e.dst, label, style).unwrap();
+        }
+        writeln!(out, "}}").unwrap();
+        out
+    }
+
+    pub fn to_graphml(&self) -> String {
+        let mut out = String::new();
+        writeln!(out, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>").unwrap();
+        writeln!(out, "<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">").unwrap();
+        for (id, target, name, ty) in &[
+            ("score", "node", "score", "long"),
+            ("target", "node", "target", "boolean"),
+            ("solved", "node", "solved", "boolean"),
+            ("escore", "edge", "score", "long"),
+            ("hits", "edge", "hits", "long"),
+            ("callsites", "edge", "callsites", "string"),
+            ("magic", "edge", "magic_bytes", "string"),
+        ] {
+            writeln!(
+                out,
+                "  <key id=\"{}\" for=\"{}\" attr.name=\"{}\" attr.type=\"{}\"/>",
+                id, target, name, ty
+            )
+            .unwrap();
+        }
+        writeln!(out, "  <graph id=\"cfg\" edgedefault=\"directed\">").unwrap();
+        for n in &self.nodes {
+            writeln!(out, "    <node id=\"{}\">", n.id).unwrap();
+            if let Some(s) = n.score {
+                writeln!(out, "      <data key=\"score\">{}</data>", s).unwrap();
+            }
+            writeln!(out, "      <data key=\"target\">{}</data>", n.target).unwrap();
+            writeln!(out, "      <data key=\"solved\">{}</data>", n.solved).unwrap();
+            writeln!(out, "    </node>").unwrap();
+        }
+        for e in &self.edges {
+            writeln!(out, "    <edge source=\"{}\" target=\"{}\">", e.src, e.dst).unwrap();
+            if let Some(s) = e.score {
+                writeln!(out, "      <data key=\"escore\">{}</data>", s).unwrap();
+            }
+            writeln!(out, "      <data key=\"hits\">{}</data>", e.hits).unwrap();
+            writeln!(out, "      <data key=\"callsites\">{:?}</data>", e.callsites).unwrap();
+            writeln!(out, "      <data key=\"magic\">{:?}</data>", e.magic_bytes).unwrap();
+            writeln!(out, "    </edge>").unwrap();
+        }
+        writeln!(out, "  </graph>").unwrap();
+        writeln!(out, "</graphml>").unwrap();
+        out
+    }
+}
+
+// Replace the file at once, it may be open while the campaign runs.
+fn write_file(path: &Path, data: &[u8]) -> io::Result<()> {
+    let tmp_path = path.with_extension("tmp");
+    fs::write(&tmp_path, data)?;
+    fs::rename(&tmp_path, path)
+}
+
+pub fn write_cfg_export(dir: &Path, export: &CfgExport) -> io::Result<()> {
+    write_file(&dir.join(defs::CFG_EXPORT_JSON_FILE), &serde_json::to_vec(export)?)?;
+    write_file(&dir.join(defs::CFG_EXPORT_DOT_FILE), export.to_dot().as_bytes())?;
+    write_file(&dir.join(defs::CFG_EXPORT_GRAPHML_FILE), export.to_graphml().as_bytes())
+}
+
+// The edges of an exported cfg, to add to the edges of the cfg file.
+pub fn read_export_edges(path: &Path) -> io::Result<HashSet<Edge>> {
+    let export: CfgExport = serde_json::from_slice(&fs::read(path)?)?;
+    Ok(export.cfg_edges())
+}
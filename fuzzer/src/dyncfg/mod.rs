@@ -1,5 +1,7 @@
 pub mod cfg;
+pub mod export;
 pub mod fparse;
+pub mod score;
 
 #[cfg(test)]
 mod tests {
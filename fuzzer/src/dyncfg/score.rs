@@ -0,0 +1,20 @@
+// How the distance of a cmp to the targets is aggregated from the distances of its successors.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub enum ScoreStrategy {
+    #[default]
+    Harmonic,
+    Min,
+    Coverage,
+    // Harmonic mean weighted by how often each edge was seen, like AFLGo.
+    Weighted,
+}
+
+pub fn parse_score_strategy(s: &str) -> ScoreStrategy {
+    match s {
+        "harmonic" => ScoreStrategy::Harmonic,
+        "min" => ScoreStrategy::Min,
+        "coverage" => ScoreStrategy::Coverage,
+        "weighted" => ScoreStrategy::Weighted,
+        _ => panic!("Unknown distance strategy {:?}, use harmonic, min, coverage or weighted", s),
+    }
+}
@@ -0,0 +1,232 @@
+// AFL's fork server, for programs built by afl-clang-fast or afl-clang-lto (AFL and AFL++).
+// The fuzzer writes to FORKSRV_FD, and reads the pid and the status of each child from FORKSRV_FD + 1.
+// The coverage goes to our branches map, via __AFL_SHM_ID.
+use super::{limit::SetLimit, *};
+use angora_common::{config, defs};
+use libc;
+use std::{
+    cmp,
+    collections::HashMap,
+    fs::File,
+    io::prelude::*,
+    os::unix::io::{FromRawFd, RawFd},
+    process::{Child, Command, Stdio},
+};
+
+// Not in angora_common: the strings there end up in every program linked with our runtime,
+// and check_dep looks for __AFL_SHM_ID to detect AFL programs.
+pub static AFL_SHM_ENV_VAR: &str = "__AFL_SHM_ID";
+pub static AFL_MAP_SIZE_VAR: &str = "AFL_MAP_SIZE";
+pub const FORKSRV_FD: RawFd = 198;
+
+// AFL++ >= 4.20: the hello is "AFL" + version, followed by options.
+const FS_NEW_HELLO: u32 = 0x4146_4c00;
+const FS_NEW_OPT_MAPSIZE: u32 = 0x0000_0001;
+const FS_NEW_OPT_SHDMEM_FUZZ: u32 = 0x0000_0002;
+const FS_NEW_OPT_AUTODICT: u32 = 0x0000_0800;
+// Older AFL++: options in the hello itself.
+const FS_OPT_ENABLED: u32 = 0x8000_0001;
+const FS_OPT_MAPSIZE: u32 = 0x4000_0000;
+const FS_OPT_AUTODICT: u32 = 0x1000_0000;
+
+const INIT_TIMEOUT: u64 = 10000; // ms
+
+pub struct AflForksrv {
+    child: Child,
+    ctl: File,
+    st: File,
+    st_fd: RawFd,
+    uses_asan: bool,
+    time_limit: u64,
+    last_timeout: bool,
+}
+
+fn cloexec_pipe() -> (RawFd, RawFd) {
+    let mut fds = [0; 2];
+    if unsafe { libc::pipe2(fds.as_mut_ptr(), libc::O_CLOEXEC) } < 0 {
+        panic!("pipe2() failed");
+    }
+    (fds[0], fds[1])
+}
+
+impl AflForksrv {
+    pub fn new(
+        target: &(String, Vec<String>),
+        envs: &HashMap<String, String>,
+        fd: RawFd,
+        is_stdin: bool,
+        uses_asan: bool,
+        time_limit: u64,
+        mem_limit: u64,
+    ) -> Result<AflForksrv, String> {
+        let (ctl_read, ctl_write) = cloexec_pipe();
+        let (st_read, st_write) = cloexec_pipe();
+
+        let child = Command::new(&target.0)
+            .args(&target.1)
+            .stdin(Stdio::null())
+            .envs(envs)
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .mem_limit(mem_limit)
+            .setsid()
+            .pipe_stdin(fd, is_stdin)
+            .forksrv_fds(ctl_read, st_write)
+            .spawn();
+
+        let (ctl, st) = unsafe {
+            libc::close(ctl_read);
+            libc::close(st_write);
+            (File::from_raw_fd(ctl_write), File::from_raw_fd(st_read))
+        };
+        let child = child.map_err(|e| format!("Failed to spawn child: {}", e))?;
+
+        let mut fs = AflForksrv {
+            child,
+            ctl,
+            st,
+            st_fd: st_read,
+            uses_asan,
+            time_limit,
+            last_timeout: false,
+        };
+        fs.handshake()?;
+        debug!("All right -- Init AFL ForkServer successfully!");
+        Ok(fs)
+    }
+
+    fn handshake(&mut self) -> Result<(), String> {
+        let timeout = cmp::max(self.time_limit * 10, INIT_TIMEOUT);
+        let hello = self
+            .read_u32(timeout)
+            .ok_or("No hello from the AFL fork server, is the program built by AFL?")?;
+
+        if hello & 0xffff_ff00 == FS_NEW_HELLO {
+            self.write_u32(hello ^ 0xffff_ffff)?;
+            let opts = self.read_u32(timeout).ok_or("Could not read fork server options")?;
+            if opts & FS_NEW_OPT_MAPSIZE != 0 {
+                let map_size = self.read_u32(timeout).ok_or("Could not read map size")?;
+                Self::check_map_size(map_size as usize)?;
+            }
+            if opts & FS_NEW_OPT_SHDMEM_FUZZ != 0 {
+                return Err("Shared memory test cases of AFL++ are not supported".to_string());
+            }
+            if opts & FS_NEW_OPT_AUTODICT != 0 {
+                self.skip_dict(timeout)?;
+            }
+            let end = self.read_u32(timeout).ok_or("Could not finish the handshake")?;
+            if end != hello && end != hello - FS_NEW_HELLO {
+                return Err(format!("Unexpected end of handshake: {:08x}", end));
+            }
+        } else if hello & FS_OPT_ENABLED == FS_OPT_ENABLED {
+            if hello & FS_OPT_MAPSIZE != 0 {
+                let map_size = ((hello & 0x00ff_fffe) >> 1) + 1;
+                Self::check_map_size(map_size as usize)?;
+            }
+            if hello & FS_OPT_AUTODICT != 0 {
+                self.write_u32(FS_OPT_ENABLED | FS_OPT_AUTODICT)?;
+                self.skip_dict(timeout)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn check_map_size(map_size: usize) -> Result<(), String> {
+        if map_size > config::BRANCHES_SIZE {
+            return Err(format!(
+                "The map of the program ({}) is larger than ours ({}), increase MAP_SIZE_POW2",
+                map_size,
+                config::BRANCHES_SIZE
+            ));
+        }
+        Ok(())
+    }
+
+    // We don't use the dictionary of afl-clang-lto.
+    fn skip_dict(&mut self, timeout: u64) -> Result<(), String> {
+        let len = self.read_u32(timeout).ok_or("Could not read dictionary length")?;
+        let mut dict = vec![0; len as usize];
+        self.st
+            .read_exact(&mut dict)
+            .map_err(|e| format!("Could not read dictionary: {}", e))
+    }
+
+    fn write_u32(&mut self, v: u32) -> Result<(), String> {
+        self.ctl
+            .write_all(&v.to_le_bytes())
+            .map_err(|e| format!("Could not write to fork server: {}", e))
+    }
+
+    // None if timeout or the fork server is gone.
+    fn read_u32(&mut self, timeout: u64) -> Option<u32> {
+        let mut pfd = libc::pollfd {
+            fd: self.st_fd,
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        let timeout = cmp::min(timeout, libc::c_int::MAX as u64) as libc::c_int;
+        if unsafe { libc::poll(&mut pfd, 1, timeout) } <= 0 {
+            return None;
+        }
+        let mut buf = [0; 4];
+        self.st.read_exact(&mut buf).ok()?;
+        Some(u32::from_le_bytes(buf))
+    }
+
+    pub fn run(&mut self) -> StatusType {
+        // A stopped persistent child is killed by the fork server if it timed out.
+        let prev_timeout = self.last_timeout as u32;
+        self.last_timeout = false;
+        if self.write_u32(prev_timeout).is_err() {
+            warn!("Fail to write to AFL fork server!!");
+            return StatusType::Error;
+        }
+
+        let child_pid = match self.read_u32(self.time_limit) {
+            Some(pid) if pid as i32 > 0 => pid as i32,
+            _ => {
+                warn!("Unable to request new process from AFL fork server!");
+                return StatusType::Error;
+            },
+        };
+
+        let status = match self.read_u32(self.time_limit) {
+            Some(status) => status as i32,
+            None => {
+                unsafe {
+                    libc::kill(child_pid, libc::SIGKILL);
+                }
+                self.last_timeout = true;
+                if self.read_u32(INIT_TIMEOUT).is_none() {
+                    warn!("Could not get the status of the killed process");
+                    return StatusType::Error;
+                }
+                return StatusType::Timeout;
+            },
+        };
+
+        let exit_code = unsafe { libc::WEXITSTATUS(status) };
+        let signaled = unsafe { libc::WIFSIGNALED(status) };
+        let stopped = unsafe { libc::WIFSTOPPED(status) };
+        if stopped {
+            StatusType::Normal
+        } else if signaled || (self.uses_asan && exit_code == defs::MSAN_ERROR_CODE) {
+            debug!("Crash code: {}", status);
+            StatusType::Crash
+        } else {
+            StatusType::Normal
+        }
+    }
+}
+
+impl Drop for AflForksrv {
+    fn drop(&mut self) {
+        debug!("Exit AFL Forksrv");
+        // The fork server runs in its own session, kill its process group with the child.
+        unsafe {
+            libc::kill(-(self.child.id() as i32), libc::SIGKILL);
+        }
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
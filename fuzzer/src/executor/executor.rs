@@ -4,14 +4,16 @@ use crate::{
     branches, command,
     cond_stmt::{self, NextState},
     depot, stats, track,
-    dyncfg::cfg::{CmpId},
+    dyncfg::cfg::{CmpId, Edge},
+    triage::{self, CrashReport, Verification},
 };
 use angora_common::{config, defs, tag::TagSeg};
 
 use std::{
     collections::{HashSet, HashMap},
+    fs,
     path::Path,
-    process::{Command, Stdio},
+    process::{Child, Command, Stdio},
     sync::{
         atomic::{compiler_fence, Ordering},
         Arc, RwLock, Mutex
@@ -27,14 +29,18 @@ pub struct Executor {
     pub branches: branches::Branches,
     pub t_conds: cond_stmt::ShmConds,
     envs: HashMap<String, String>,
-    forksrv: Option<Forksrv>,
-    depot: Arc<depot::Depot>,
+    forksrv: Option<MainForksrv>,
+    track_forksrv: Option<Forksrv>,
+    pub depot: Arc<depot::Depot>,
     fd: PipeFd,
+    shm_input: Option<ShmInput>,
     tmout_cnt: usize,
     invariable_cnt: usize,
     pub last_f: u64,
     pub func_rel_map : Arc<RwLock<Box<[Box<[usize]>]>>>,
     pub has_new_path: bool,
+    // us, the slowest input with new paths, for -T auto
+    pub slowest_exec_us: u32,
     pub global_stats: Arc<RwLock<stats::ChartStats>>,
     pub local_stats: stats::LocalStats,
     is_directed: bool,
@@ -76,18 +82,32 @@ impl Executor {
             defs::LD_LIBRARY_PATH_VAR.to_string(),
             cmd.ld_library.clone(),
         );
+        let shm_input = if cmd.shm_input {
+            let shm_input = ShmInput::new();
+            envs.insert(
+                defs::INPUT_SHM_ENV_VAR.to_string(),
+                shm_input.get_id().to_string(),
+            );
+            Some(shm_input)
+        } else {
+            None
+        };
+        if cmd.is_persistent {
+            envs.insert(defs::PERSIST_ENV_VAR.to_string(), String::from("1"));
+        }
+        if cmd.afl_forksrv {
+            envs.insert(
+                afl_forksrv::AFL_SHM_ENV_VAR.to_string(),
+                branches.get_id().to_string(),
+            );
+            envs.insert(
+                afl_forksrv::AFL_MAP_SIZE_VAR.to_string(),
+                config::BRANCHES_SIZE.to_string(),
+            );
+        }
 
         let fd = pipe_fd::PipeFd::new(&cmd.out_file);
-        let forksrv = Some(forksrv::Forksrv::new(
-            &cmd.forksrv_socket_path,
-            &cmd.main,
-            &envs,
-            fd.as_raw_fd(),
-            cmd.is_stdin,
-            cmd.uses_asan,
-            cmd.time_limit,
-            cmd.mem_limit,
-        ));
+        let forksrv = MainForksrv::new(&cmd, &envs, &fd);
 
         let is_directed = cmd.directed_only;
 
@@ -97,12 +117,15 @@ impl Executor {
             t_conds,
             envs,
             forksrv,
+            track_forksrv: None,
             depot,
             fd,
+            shm_input,
             tmout_cnt: 0,
             invariable_cnt: 0,
             last_f: defs::UNREACHABLE,
             has_new_path: false,
+            slowest_exec_us: 0,
             global_stats,
             local_stats: Default::default(),
             is_directed,
@@ -120,17 +143,28 @@ impl Executor {
             // delete the old forksrv
             self.forksrv = None;
         }
-        let fs = forksrv::Forksrv::new(
-            &self.cmd.forksrv_socket_path,
-            &self.cmd.main,
-            &self.envs,
-            self.fd.as_raw_fd(),
-            self.cmd.is_stdin,
-            self.cmd.uses_asan,
-            self.cmd.time_limit,
-            self.cmd.mem_limit,
-        );
-        self.forksrv = Some(fs);
+        self.forksrv = MainForksrv::new(&self.cmd, &self.envs, &self.fd);
+    }
+
+    pub fn set_time_limit(&mut self, time_limit: u64) {
+        self.cmd.set_time_limit(time_limit);
+        self.rebind_forksrv();
+        self.track_forksrv = None;
+    }
+
+    // Started lazily, since only inputs with new paths are tracked.
+    fn start_track_forksrv(&mut self) {
+        if self.track_forksrv.is_none() && self.cmd.enable_track_forksrv {
+            self.track_forksrv = Some(forksrv::Forksrv::new_track(
+                &self.cmd.track_forksrv_socket_path,
+                &self.cmd.track,
+                &self.envs,
+                self.fd.as_raw_fd(),
+                self.cmd.is_stdin,
+                self.cmd.time_limit_track,
+                config::MEM_LIMIT_TRACK,
+            ));
+        }
     }
 
     // FIXME: The location id may be inconsistent between track and fast programs.
@@ -233,7 +267,11 @@ impl Executor {
             );
             // crash or hang
             if self.branches.has_new(unmem_status, self.is_directed).0 {
-                self.depot.save(unmem_status, &buf, cmpid);
+                if unmem_status == StatusType::Crash {
+                    self.save_crash(buf, cmpid);
+                } else {
+                    self.depot.save(unmem_status, &buf, cmpid);
+                }
             }
         }
         skip
@@ -246,11 +284,23 @@ impl Executor {
         if has_new_path {
             self.has_new_path = true;
             self.local_stats.find_new(&status);
+            let reached_targets = self.branches.get_reached_targets();
+            if status == StatusType::Crash {
+                self.depot.targets.reach(&reached_targets, None);
+                self.save_crash(buf, cmpid);
+                return;
+            }
             let id = self.depot.save(status, &buf, cmpid);
+            if status == StatusType::Normal {
+                self.depot.targets.reach(&reached_targets, Some(id));
+            }
 
             if status == StatusType::Normal {
                 self.local_stats.avg_edge_num.update(edge_num as f32);
                 let speed = self.count_time();
+                if speed != defs::SLOW_SPEED && speed > self.slowest_exec_us {
+                    self.slowest_exec_us = speed;
+                }
                 let speed_ratio = self.local_stats.avg_exec_time.get_ratio(speed as f32);
                 self.local_stats.avg_exec_time.update(speed as f32);
 
@@ -287,10 +337,11 @@ impl Executor {
         self.check_timeout(status, cond)
     }
 
-    pub fn run_sync(&mut self, buf: &Vec<u8>) {
+    pub fn run_sync(&mut self, buf: &Vec<u8>) -> StatusType {
         self.run_init();
         let status = self.run_inner(buf);
         self.do_if_has_new(buf, status, false, 0, 0);
+        status
     }
 
     fn run_init(&mut self) {
@@ -351,6 +402,12 @@ impl Executor {
         };
         compiler_fence(Ordering::SeqCst);
 
+        if ret_status == StatusType::Normal {
+            if let Some(ref mut shm_input) = self.shm_input {
+                shm_input.check_fetched();
+            }
+        }
+
         ret_status
     }
 
@@ -385,15 +442,27 @@ impl Executor {
 
         let t_now: stats::TimeIns = Default::default();
 
-        self.write_test(buf);
+        self.write_file(buf);
+
+        self.start_track_forksrv();
 
         compiler_fence(Ordering::SeqCst);
-        let ret_status = self.run_target(
-            &self.cmd.track,
-            config::MEM_LIMIT_TRACK,
-            //self.cmd.time_limit *
-            config::TIME_LIMIT_TRACK,
-        );
+        let mut ret_status = match self.track_forksrv {
+            Some(ref mut fs) => fs.run_track(&self.cmd.track_path),
+            None => StatusType::Error,
+        };
+        if ret_status == StatusType::Error {
+            // No fork server, or it died: run the track program directly this time.
+            self.track_forksrv = None;
+            if self.cmd.is_stdin {
+                self.fd.rewind();
+            }
+            ret_status = self.run_target(
+                &self.cmd.track,
+                config::MEM_LIMIT_TRACK,
+                self.cmd.time_limit_track,
+            );
+        }
         compiler_fence(Ordering::SeqCst);
 
         if ret_status != StatusType::Normal {
@@ -414,18 +483,23 @@ impl Executor {
 
         let mut ind_dominator_offsets : HashMap<CmpId, Vec<TagSeg>> = HashMap::new();
         let mut ind_cond_list = vec![];
-
-
-        for (a,b) in cond_list.clone().into_iter().tuple_windows() {
-            let mut dyncfg = self.depot.cfg.write().unwrap();
+        let edges: Vec<Edge> = cond_list
+            .iter()
+            .tuple_windows()
+            .map(|(a, b)| (a.base.cmpid, b.base.cmpid))
+            .collect();
+        // All the edges of the run at once, the scores are updated in one batch.
+        let mut dyncfg = self.depot.cfg.write().unwrap();
+        dyncfg.add_run_edges(&edges);
+
+        for (a,b) in cond_list.iter().tuple_windows() {
             let edge = (a.base.cmpid, b.base.cmpid);
-            let _is_new = dyncfg.add_edge(edge);
 
             // Collect indirect call dominator taint
             if dyncfg.dominates_indirect_call(a.base.cmpid) {
                 let entry = ind_dominator_offsets.entry(a.base.cmpid).or_insert(vec![]);
                 debug!("OFFSET set {} {:?}", a.base.cmpid, a.offsets);
-                *entry = a.offsets;
+                *entry = a.offsets.clone();
             }
 
             debug!("VARIABLES: {:?}", a.variables);
@@ -457,6 +531,7 @@ impl Executor {
             }
 
         }
+        drop(dyncfg);
 
 
         for cond in cond_list.iter_mut() {
@@ -479,37 +554,160 @@ impl Executor {
     }
 
     fn write_test(&mut self, buf: &Vec<u8>) {
+        if let Some(ref mut shm_input) = self.shm_input {
+            shm_input.write_buf(buf);
+            return;
+        }
+        self.write_file(buf);
+    }
+
+    // The track program always reads the input file, so that the input bytes are tainted.
+    fn write_file(&mut self, buf: &Vec<u8>) {
         self.fd.write_buf(buf);
         if self.cmd.is_stdin {
             self.fd.rewind();
         }
     }
 
-    fn run_target(
+    // Keep one crashing input (and its report) per unique bug.
+    // Crashes that don't reproduce every time go to crashes/flaky.
+    fn save_crash(&mut self, buf: &Vec<u8>, cmpid: u32) {
+        let verification = self.verify_crash(buf);
+        let mut report = self.reproduce_crash(buf);
+        report.verification = Some(verification);
+        if !self.depot.bugs.add(&report, verification.is_deterministic()) {
+            debug!("Duplicate crash: {:?}", report.bug_type);
+            return;
+        }
+        let report_path = if verification.is_deterministic() {
+            let id = self.depot.save(StatusType::Crash, buf, cmpid);
+            depot::get_file_name(&self.depot.dirs.crashes_dir, id)
+        } else {
+            warn!("Flaky crash, reproduced: {}", verification);
+            let id = self.depot.save_flaky(buf, cmpid);
+            depot::get_file_name(&self.depot.dirs.flaky_dir, id)
+        }
+        .with_extension(defs::CRASH_REPORT_EXT);
+        if let Err(e) = report.save(&report_path) {
+            warn!("Could not save crash report {:?}: {:?}", report_path, e);
+        }
+    }
+
+    // Rerun the crashing input on the fast program, and on the sanopt program if there is one.
+    fn verify_crash(&mut self, buf: &Vec<u8>) -> Verification {
+        self.write_file(buf);
+        let runs = config::CRASH_VERIFY_RUNS;
+        let fast_target = self.cmd.fast.clone();
+        let fast = self.count_crashes(&fast_target, self.cmd.fast_uses_asan, self.cmd.time_limit, runs);
+        let sanopt = match self.cmd.sanopt_bin.clone() {
+            Some(bin) if bin != fast_target.0 => {
+                let target = (bin, fast_target.1.clone());
+                Some(self.count_crashes(&target, true, self.cmd.time_limit_sanopt, runs))
+            },
+            _ => None,
+        };
+        Verification { runs, fast, sanopt }
+    }
+
+    fn count_crashes(
+        &mut self,
+        target: &(String, Vec<String>),
+        uses_asan: bool,
+        time_limit: u64,
+        runs: usize,
+    ) -> usize {
+        let mem_limit = if uses_asan { 0 } else { self.cmd.mem_limit };
+        let mut num = 0;
+        for _ in 0..runs {
+            if self.cmd.is_stdin {
+                self.fd.rewind();
+            }
+            if self.run_target_as(target, uses_asan, mem_limit, time_limit) == StatusType::Crash {
+                num += 1;
+            }
+        }
+        num
+    }
+
+    // Run the crashing input again with symbolized sanitizer output,
+    // on the sanopt program if there is one: the fast one usually has no sanitizer.
+    pub fn reproduce_crash(&mut self, buf: &Vec<u8>) -> CrashReport {
+        self.write_file(buf);
+        let (target, mem_limit) = match self.cmd.sanopt_bin.clone() {
+            Some(bin) => ((bin, self.cmd.main.1.clone()), 0),
+            None => {
+                let mem_limit = if self.cmd.uses_asan { 0 } else { self.cmd.mem_limit };
+                (self.cmd.main.clone(), mem_limit)
+            },
+        };
+
+        let envs = triage::report_envs(&self.envs);
+        let stderr_path = format!("{}_stderr", self.cmd.out_file);
+        let stderr = match fs::File::create(&stderr_path) {
+            Ok(f) => Stdio::from(f),
+            Err(e) => {
+                warn!("Could not create {}: {:?}", stderr_path, e);
+                Stdio::null()
+            }
+        };
+
+        let mut child = self.spawn_target(&target, mem_limit, &envs, stderr);
+        let timeout = time::Duration::from_millis(self.cmd.time_limit_sanopt);
+        let status = child.wait_timeout(timeout).unwrap();
+        if status.is_none() {
+            child.kill().expect("Could not send kill signal to child.");
+            child.wait().expect("Error during waiting for child.");
+        }
+        let status = triage::describe_status(status);
+
+        let stderr = fs::read(&stderr_path).unwrap_or_default();
+        CrashReport::parse(&String::from_utf8_lossy(&stderr), &status)
+    }
+
+    fn spawn_target(
         &self,
         target: &(String, Vec<String>),
         mem_limit: u64,
-        time_limit: u64,
-    ) -> StatusType {
+        envs: &HashMap<String, String>,
+        stderr: Stdio,
+    ) -> Child {
         let mut cmd = Command::new(&target.0);
-        let mut child = cmd
-            .args(&target.1)
+        cmd.args(&target.1)
             .stdin(Stdio::null())
             .env_clear()
-            .envs(&self.envs)
+            .envs(envs)
             .stdout(Stdio::null())
-            .stderr(Stdio::null())
-            .mem_limit(mem_limit.clone())
+            .stderr(stderr)
+            .mem_limit(mem_limit)
             .setsid()
             .pipe_stdin(self.fd.as_raw_fd(), self.cmd.is_stdin)
             .spawn()
-            .expect("Could not run target");
+            .expect("Could not run target")
+    }
+
+    fn run_target(
+        &self,
+        target: &(String, Vec<String>),
+        mem_limit: u64,
+        time_limit: u64,
+    ) -> StatusType {
+        self.run_target_as(target, self.cmd.uses_asan, mem_limit, time_limit)
+    }
+
+    fn run_target_as(
+        &self,
+        target: &(String, Vec<String>),
+        uses_asan: bool,
+        mem_limit: u64,
+        time_limit: u64,
+    ) -> StatusType {
+        let mut child = self.spawn_target(target, mem_limit, &self.envs, Stdio::null());
 
-        let timeout = time::Duration::from_secs(time_limit);
+        let timeout = time::Duration::from_millis(time_limit);
         let ret = match child.wait_timeout(timeout).unwrap() {
             Some(status) => {
                 if let Some(status_code) = status.code() {
-                    if (self.cmd.uses_asan && status_code == defs::MSAN_ERROR_CODE)
+                    if (uses_asan && status_code == defs::MSAN_ERROR_CODE)
                         || (self.cmd.mode.is_pin_mode() && status_code > 128)
                     {
                         StatusType::Crash
@@ -36,6 +36,37 @@ impl Forksrv {
         uses_asan: bool,
         time_limit: u64,
         mem_limit: u64,
+    ) -> Forksrv {
+        let mut envs_fk = envs.clone();
+        envs_fk.insert(ENABLE_FORKSRV.to_string(), String::from("TRUE"));
+        envs_fk.insert(FORKSRV_SOCKET_PATH_VAR.to_string(), socket_path.to_owned());
+        Self::spawn(socket_path, target, &envs_fk, fd, is_stdin, uses_asan, time_limit, mem_limit)
+    }
+
+    // Fork server of the track program, see runtime/src/forkcli.rs
+    pub fn new_track(
+        socket_path: &str,
+        target: &(String, Vec<String>),
+        envs: &HashMap<String, String>,
+        fd: RawFd,
+        is_stdin: bool,
+        time_limit: u64,
+        mem_limit: u64,
+    ) -> Forksrv {
+        let mut envs_fk = envs.clone();
+        envs_fk.insert(TRACK_FORKSRV_SOCKET_PATH_VAR.to_string(), socket_path.to_owned());
+        Self::spawn(socket_path, target, &envs_fk, fd, is_stdin, false, time_limit, mem_limit)
+    }
+
+    fn spawn(
+        socket_path: &str,
+        target: &(String, Vec<String>),
+        envs_fk: &HashMap<String, String>,
+        fd: RawFd,
+        is_stdin: bool,
+        uses_asan: bool,
+        time_limit: u64,
+        mem_limit: u64,
     ) -> Forksrv {
         debug!("socket_path: {:?}", socket_path);
         let listener = match UnixListener::bind(socket_path) {
@@ -46,16 +77,12 @@ impl Forksrv {
             }
         };
 
-        let mut envs_fk = envs.clone();
-        envs_fk.insert(ENABLE_FORKSRV.to_string(), String::from("TRUE"));
-        envs_fk.insert(FORKSRV_SOCKET_PATH_VAR.to_string(), socket_path.to_owned());
-
         debug!("target.0 : {}", target.0);
         debug!("target.1 : {:?}", target.1);
         match Command::new(&target.0)
             .args(&target.1)
             .stdin(Stdio::null())
-            .envs(&envs_fk)
+            .envs(envs_fk)
             .stdout(Stdio::null())
             .stderr(Stdio::null())
             .mem_limit(mem_limit.clone())
@@ -84,10 +111,10 @@ impl Forksrv {
         debug!("listener accepted");
 
         socket
-            .set_read_timeout(Some(Duration::from_secs(time_limit)))
+            .set_read_timeout(Some(Duration::from_millis(time_limit)))
             .expect("Couldn't set read timeout");
         socket
-            .set_write_timeout(Some(Duration::from_secs(time_limit)))
+            .set_write_timeout(Some(Duration::from_millis(time_limit)))
             .expect("Couldn't set write timeout");
 
         debug!("All right -- Init ForkServer {} successfully!", socket_path);
@@ -108,6 +135,25 @@ impl Forksrv {
             return StatusType::Error;
         }
 
+        self.wait_child()
+    }
+
+    // Run the track program, which writes the track data to track_path.
+    pub fn run_track(&mut self, track_path: &str) -> StatusType {
+        debug!("forksrv run track");
+
+        let mut msg = FORKSRV_NEW_CHILD.to_vec();
+        msg.extend_from_slice(&(track_path.len() as u32).to_le_bytes());
+        msg.extend_from_slice(track_path.as_bytes());
+        if self.socket.write_all(&msg).is_err() {
+            warn!("Fail to write socket!!");
+            return StatusType::Error;
+        }
+
+        self.wait_child()
+    }
+
+    fn wait_child(&mut self) -> StatusType {
         let mut buf = vec![0; 4];
         let child_pid: i32;
         match self.socket.read(&mut buf) {
@@ -148,7 +194,11 @@ impl Forksrv {
                 };
                 let exit_code = unsafe { libc::WEXITSTATUS(status) };
                 let signaled = unsafe { libc::WIFSIGNALED(status) };
-                if signaled || (self.uses_asan && exit_code == MSAN_ERROR_CODE) {
+                // A persistent child stops itself after each input.
+                let stopped = unsafe { libc::WIFSTOPPED(status) };
+                if stopped {
+                    StatusType::Normal
+                } else if signaled || (self.uses_asan && exit_code == MSAN_ERROR_CODE) {
                     debug!("Crash code: {}", status);
                     StatusType::Crash
                 } else {
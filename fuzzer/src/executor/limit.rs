@@ -8,6 +8,7 @@ pub trait SetLimit {
     fn mem_limit(&mut self, size: u64) -> &mut Self;
     fn setsid(&mut self) -> &mut Self;
     fn pipe_stdin(&mut self, fd: RawFd, is_stdin: bool) -> &mut Self;
+    fn forksrv_fds(&mut self, ctl_fd: RawFd, st_fd: RawFd) -> &mut Self;
     //fn dup2(&mut self, src: libc::c_int, dst: libc::c_int) -> &mut Self;
     //fn close_fd(&mut self, fd: libc::c_int) -> &mut Self;
 }
@@ -70,4 +71,16 @@ impl SetLimit for Command {
             self
         }
     }
+
+    // The control and status pipes of AFL's fork server.
+    fn forksrv_fds(&mut self, ctl_fd: RawFd, st_fd: RawFd) -> &mut Self {
+        let func = move || {
+            let fd = super::afl_forksrv::FORKSRV_FD;
+            if unsafe { libc::dup2(ctl_fd, fd) } < 0 || unsafe { libc::dup2(st_fd, fd + 1) } < 0 {
+                panic!("dup2() failded");
+            }
+            Ok(())
+        };
+        unsafe { self.pre_exec(func) }
+    }
 }
@@ -0,0 +1,48 @@
+use super::*;
+use crate::command;
+use std::collections::HashMap;
+
+// The fork server of the fast (or sanopt) program: ours, or AFL's for programs built by AFL.
+pub enum MainForksrv {
+    Angora(Forksrv),
+    Afl(AflForksrv),
+}
+
+impl MainForksrv {
+    pub fn new(cmd: &command::CommandOpt, envs: &HashMap<String, String>, fd: &PipeFd) -> Option<Self> {
+        if !cmd.afl_forksrv {
+            return Some(MainForksrv::Angora(Forksrv::new(
+                &cmd.forksrv_socket_path,
+                &cmd.main,
+                envs,
+                fd.as_raw_fd(),
+                cmd.is_stdin,
+                cmd.uses_asan,
+                cmd.time_limit,
+                cmd.mem_limit,
+            )));
+        }
+        match AflForksrv::new(
+            &cmd.main,
+            envs,
+            fd.as_raw_fd(),
+            cmd.is_stdin,
+            cmd.uses_asan,
+            cmd.time_limit,
+            cmd.mem_limit,
+        ) {
+            Ok(fs) => Some(MainForksrv::Afl(fs)),
+            Err(e) => {
+                warn!("Could not start AFL fork server, run without it: {}", e);
+                None
+            },
+        }
+    }
+
+    pub fn run(&mut self) -> StatusType {
+        match self {
+            MainForksrv::Angora(fs) => fs.run(),
+            MainForksrv::Afl(fs) => fs.run(),
+        }
+    }
+}
@@ -1,8 +1,12 @@
+mod afl_forksrv;
 mod executor;
 mod forksrv;
 mod limit;
+mod main_forksrv;
 mod pipe_fd;
+mod runner;
+mod shm_input;
 mod status_type;
 
-use self::pipe_fd::PipeFd;
-pub use self::{executor::Executor, forksrv::Forksrv, status_type::StatusType};
+use self::{afl_forksrv::AflForksrv, main_forksrv::MainForksrv, pipe_fd::PipeFd, shm_input::ShmInput};
+pub use self::{executor::Executor, forksrv::Forksrv, runner::Runner, status_type::StatusType};
@@ -0,0 +1,250 @@
+// Run the fast program on single inputs and get their branch maps,
+// for the tools outside of the fuzzing loop (cmin, tmin, ...).
+use super::{limit::SetLimit, *};
+use crate::{
+    branches::{self, BranchBuf},
+    check_dep, command, cond_stmt, tmpfs, track,
+    triage::{self, CrashReport},
+};
+use angora_common::{config, defs, shm::SHM};
+use libc;
+use std::{
+    collections::HashMap,
+    env, fs,
+    path::PathBuf,
+    process::{Command, ExitStatus, Stdio},
+    time,
+};
+use wait_timeout::ChildExt;
+
+static INPUT_FILE: &str = "cur_input";
+static FORKSRV_SOCKET_FILE: &str = "forksrv_socket";
+static TRACK_FILE: &str = "track";
+
+pub struct Runner {
+    main: (String, Vec<String>),
+    envs: HashMap<String, String>,
+    tmp_dir: PathBuf,
+    socket_path: String,
+    is_stdin: bool,
+    uses_asan: bool,
+    afl_forksrv: bool,
+    pub time_limit: u64,
+    pub mem_limit: u64,
+    trace: SHM<BranchBuf>,
+    t_conds: cond_stmt::ShmConds,
+    fd: PipeFd,
+    out_file: String,
+    forksrv: Option<MainForksrv>,
+}
+
+impl Runner {
+    // pargs: the program and its arguments, "@@" is replaced by the input file.
+    pub fn new(pargs: Vec<String>, time_limit: u64, mut mem_limit: u64) -> Self {
+        let pid = unsafe { libc::getpid() as usize };
+        let tmp_dir = env::temp_dir().join(format!("parmesan_tmp_{}", pid));
+        tmpfs::create_tmpfs_dir(&tmp_dir);
+        let out_file = tmp_dir.join(INPUT_FILE).to_str().unwrap().to_owned();
+        let socket_path = tmp_dir.join(FORKSRV_SOCKET_FILE).to_str().unwrap().to_owned();
+
+        let main_bin = command::make_absolute_str(&pargs[0]);
+        let is_stdin = !pargs.contains(&"@@".to_string());
+        let main_args: Vec<String> = pargs[1..]
+            .iter()
+            .map(|arg| if arg == "@@" { out_file.clone() } else { arg.clone() })
+            .collect();
+
+        let uses_asan = check_dep::check_asan(&main_bin);
+        if uses_asan && mem_limit != 0 {
+            warn!("The program compiled with ASAN, set MEM_LIMIT to 0 (unlimited)");
+            mem_limit = 0;
+        }
+        let afl_forksrv = check_dep::check_afl(&main_bin);
+
+        let trace = SHM::<BranchBuf>::new();
+        let t_conds = cond_stmt::ShmConds::new();
+        let mut envs = HashMap::new();
+        envs.insert(
+            defs::ASAN_OPTIONS_VAR.to_string(),
+            defs::ASAN_OPTIONS_CONTENT.to_string(),
+        );
+        envs.insert(
+            defs::MSAN_OPTIONS_VAR.to_string(),
+            defs::MSAN_OPTIONS_CONTENT.to_string(),
+        );
+        envs.insert(
+            defs::BRANCHES_SHM_ENV_VAR.to_string(),
+            trace.get_id().to_string(),
+        );
+        envs.insert(
+            defs::COND_STMT_ENV_VAR.to_string(),
+            t_conds.get_id().to_string(),
+        );
+        if check_dep::check_persistent(&main_bin) {
+            envs.insert(defs::PERSIST_ENV_VAR.to_string(), String::from("1"));
+        }
+        if afl_forksrv {
+            envs.insert(
+                afl_forksrv::AFL_SHM_ENV_VAR.to_string(),
+                trace.get_id().to_string(),
+            );
+            envs.insert(
+                afl_forksrv::AFL_MAP_SIZE_VAR.to_string(),
+                config::BRANCHES_SIZE.to_string(),
+            );
+        }
+
+        let fd = PipeFd::new(&out_file);
+        let mut runner = Self {
+            main: (main_bin, main_args),
+            envs,
+            tmp_dir,
+            socket_path,
+            is_stdin,
+            uses_asan,
+            afl_forksrv,
+            time_limit,
+            mem_limit,
+            trace,
+            t_conds,
+            fd,
+            out_file,
+            forksrv: None,
+        };
+        runner.start_forksrv();
+        runner
+    }
+
+    fn start_forksrv(&mut self) {
+        // Drop the old one first, it owns the socket file.
+        self.forksrv = None;
+        if self.afl_forksrv {
+            let fs = AflForksrv::new(
+                &self.main,
+                &self.envs,
+                self.fd.as_raw_fd(),
+                self.is_stdin,
+                self.uses_asan,
+                self.time_limit,
+                self.mem_limit,
+            )
+            .unwrap_or_else(|e| panic!("Could not start AFL fork server: {}", e));
+            self.forksrv = Some(MainForksrv::Afl(fs));
+        } else {
+            self.forksrv = Some(MainForksrv::Angora(Forksrv::new(
+                &self.socket_path,
+                &self.main,
+                &self.envs,
+                self.fd.as_raw_fd(),
+                self.is_stdin,
+                self.uses_asan,
+                self.time_limit,
+                self.mem_limit,
+            )));
+        }
+    }
+
+    pub fn run(&mut self, buf: &Vec<u8>) -> StatusType {
+        self.t_conds.clear();
+        self.run_inner(buf)
+    }
+
+    // Also get the output of the condition, UNREACHABLE if the run didn't reach it.
+    pub fn run_with_cond(&mut self, buf: &Vec<u8>, cond: &cond_stmt::CondStmt) -> (StatusType, u64) {
+        self.t_conds.clear();
+        self.t_conds.set(cond);
+        let status = self.run_inner(buf);
+        (status, self.t_conds.get_cond_output())
+    }
+
+    fn run_inner(&mut self, buf: &Vec<u8>) -> StatusType {
+        for _ in 0..2 {
+            self.trace.clear();
+            self.fd.write_buf(buf);
+            self.fd.rewind();
+            let status = self.forksrv.as_mut().unwrap().run();
+            if status != StatusType::Error {
+                return status;
+            }
+            warn!("The fork server is gone, restart it");
+            self.start_forksrv();
+        }
+        StatusType::Error
+    }
+
+    // Run the input without the fork server, None if it times out.
+    fn run_direct(
+        &mut self,
+        bin: &str,
+        buf: &Vec<u8>,
+        envs: &HashMap<String, String>,
+        stderr: Stdio,
+        mem_limit: u64,
+    ) -> Option<ExitStatus> {
+        self.fd.write_buf(buf);
+        self.fd.rewind();
+        let mut child = Command::new(bin)
+            .args(&self.main.1)
+            .stdin(Stdio::null())
+            .envs(envs)
+            .stdout(Stdio::null())
+            .stderr(stderr)
+            .mem_limit(mem_limit)
+            .setsid()
+            .pipe_stdin(self.fd.as_raw_fd(), self.is_stdin)
+            .spawn()
+            .expect("Could not run target");
+        let timeout = time::Duration::from_millis(self.time_limit * config::TIME_LIMIT_TRACK_FACTOR);
+        let status = child.wait_timeout(timeout).unwrap();
+        if status.is_none() {
+            child.kill().expect("Could not send kill signal to child.");
+            child.wait().expect("Error during waiting for child.");
+        }
+        status
+    }
+
+    // Run the input once more without the fork server, like Executor::reproduce_crash.
+    pub fn reproduce(&mut self, buf: &Vec<u8>) -> CrashReport {
+        let stderr_path = format!("{}_stderr", self.out_file);
+        let stderr = fs::File::create(&stderr_path).expect("Could not create stderr file");
+        let main_bin = self.main.0.clone();
+        let envs = triage::report_envs(&self.envs);
+        let status = self.run_direct(&main_bin, buf, &envs, Stdio::from(stderr), self.mem_limit);
+        let stderr = fs::read(&stderr_path).unwrap_or_default();
+        CrashReport::parse(&String::from_utf8_lossy(&stderr), &triage::describe_status(status))
+    }
+
+    // Run the track program, with the same arguments as the fast one.
+    // The conditions are the ones the fuzzer would get, after filtering.
+    pub fn track(&mut self, track_bin: &str, buf: &Vec<u8>) -> Result<Vec<cond_stmt::CondStmt>, String> {
+        let track_path = self.tmp_dir.join(TRACK_FILE);
+        let mut envs = self.envs.clone();
+        envs.insert(
+            defs::TRACK_OUTPUT_VAR.to_string(),
+            track_path.to_str().unwrap().to_owned(),
+        );
+        let track_bin = command::make_absolute_str(track_bin);
+        let status = self.run_direct(&track_bin, buf, &envs, Stdio::null(), config::MEM_LIMIT_TRACK);
+        match status {
+            Some(status) if status.code().is_some() => {
+                Ok(track::load_track_data(&track_path, 0, 0, false, true))
+            },
+            status => Err(format!(
+                "The track program did not exit normally: {}",
+                triage::describe_status(status)
+            )),
+        }
+    }
+
+    // The path of the last run, see branches::get_path.
+    pub fn get_path(&self) -> Vec<(usize, u8)> {
+        branches::get_path(&self.trace)
+    }
+}
+
+impl Drop for Runner {
+    fn drop(&mut self) {
+        self.forksrv = None;
+        tmpfs::clear_tmpfs_dir(&self.tmp_dir);
+    }
+}
@@ -0,0 +1,50 @@
+use angora_common::{config::INPUT_SHM_SIZE, shm::SHM, shm_input::ShmInputBuf};
+
+// Deliver the test case via shared memory instead of the input file.
+pub struct ShmInput {
+    input: SHM<ShmInputBuf>,
+    checked: bool,
+}
+
+impl ShmInput {
+    pub fn new() -> Self {
+        let input = SHM::<ShmInputBuf>::new();
+        if input.is_fail() {
+            error!("FATAL: Could not create shared memory for inputs");
+            panic!();
+        }
+        Self {
+            input,
+            checked: false,
+        }
+    }
+
+    pub fn get_id(&self) -> i32 {
+        self.input.get_id()
+    }
+
+    pub fn write_buf(&mut self, buf: &[u8]) {
+        let len = if buf.len() > INPUT_SHM_SIZE {
+            warn!("Input is too large for shared memory, truncated: {}", buf.len());
+            INPUT_SHM_SIZE
+        } else {
+            buf.len()
+        };
+        self.input.buf[..len].copy_from_slice(&buf[..len]);
+        self.input.len = len as u32;
+    }
+
+    // After the first normal run, make sure the harness really reads the input
+    // via __angora_input_buf, otherwise every execution would rerun the stale input file.
+    pub fn check_fetched(&mut self) {
+        if self.checked {
+            return;
+        }
+        if self.input.fetched == 0 {
+            error!("FATAL: --shm_input is set, but the program never called __angora_input_buf()");
+            error!("Please fetch the input with __angora_input_buf() in the harness, or run without --shm_input.");
+            panic!();
+        }
+        self.checked = true;
+    }
+}
@@ -107,7 +107,7 @@ pub fn fuzz_loop(
                     }
                     if handler.cond.state.is_one_byte() {
                         OneByteFuzz::new(handler).run();
-                    } else if handler.cond.state.is_det() {
+                    } else if handler.cond.state.is_det() && cmd_opt.enable_det {
                         DetFuzz::new(handler).run();
                     } else {
                         match search_method {
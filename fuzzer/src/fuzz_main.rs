@@ -1,9 +1,13 @@
 use crate::stats::*;
-use crate::dyncfg::{cfg::ControlFlowGraph, fparse::parse_targets_file};
-use angora_common::defs;
+use crate::dyncfg::{
+    cfg::ControlFlowGraph,
+    export::{read_export_edges, write_cfg_export},
+    fparse::parse_targets_file,
+    score::parse_score_strategy,
+};
+use angora_common::{config, defs};
 use chrono::prelude::Local;
 use std::{
-    collections::HashMap,
     fs,
     io::prelude::*,
     path::{Path, PathBuf},
@@ -16,11 +20,41 @@ use std::{
     ops::Deref,
 };
 
-use crate::{bind_cpu, branches, check_dep, command, depot, executor, fuzz_loop, stats};
+use crate::{bind_cpu, branches, check_dep, command, depot, executor, fuzz_loop, netsync, resume, stats};
 use ctrlc;
 use libc;
 use pretty_env_logger;
 
+// How the fuzzer shares inputs and what it learned with other fuzzers.
+pub struct SyncOpt {
+    // The output directory is in AFL's directory structure.
+    pub sync_afl: bool,
+    // With --main or --secondary: the subdirectory of the instance in the shared output directory.
+    pub instance: Option<String>,
+    pub is_secondary: bool,
+    // Import the condition queues of the other instances too.
+    pub sync_conds: bool,
+    // Foreign corpus directories.
+    pub dirs: Vec<String>,
+    // The address of the sync server of a distributed campaign.
+    pub server: Option<String>,
+    // Learned cfgs of other campaigns to start with.
+    pub import_cfgs: Vec<String>,
+    // Cfg exports (cfg_export.json) whose edges are added to the cfg file.
+    pub import_edges: Vec<String>,
+}
+
+impl SyncOpt {
+    // Our subdirectory of the output directory, if it is shared.
+    fn dir_name(&self) -> Option<&str> {
+        match &self.instance {
+            Some(name) => Some(name),
+            None if self.sync_afl => Some(defs::ANGORA_DIR_NAME),
+            None => None,
+        }
+    }
+}
+
 pub fn fuzz_main(
     mode: &str,
     in_dir: &str,
@@ -29,25 +63,49 @@ pub fn fuzz_main(
     pargs: Vec<String>,
     num_jobs: usize,
     mem_limit: u64,
-    time_limit: u64,
+    time_limit: Option<&str>,
     search_method: &str,
-    sync_afl: bool,
+    score_strategy: &str,
+    sync_opt: SyncOpt,
     enable_afl: bool,
     enable_exploitation: bool,
     cfg_input_file: &str,
     sanopt_target: Option<&str>,
     directed_only: bool,
-    num_of_func: Option<&str>
+    num_of_func: Option<&str>,
+    shm_input: bool,
 ) {
     pretty_env_logger::init();
 
     debug!("logger test");
 
-    let (seeds_dir, angora_out_dir) = initialize_directories(in_dir, out_dir, sync_afl);
-    let parmesan_info = parse_targets_file(Path::new(&cfg_input_file)).expect("Could not read cfg targets file");
-    let cfg = ControlFlowGraph::new(parmesan_info);
+    let resuming = in_dir == "-";
+    let (seeds_dir, angora_out_dir) = initialize_directories(in_dir, out_dir, sync_opt.dir_name());
+    let mut parmesan_info = parse_targets_file(Path::new(&cfg_input_file)).expect("Could not read cfg targets file");
+    for f in &sync_opt.import_edges {
+        let edges = read_export_edges(Path::new(f))
+            .unwrap_or_else(|e| panic!("Could not import the cfg export {:?}: {:?}", f, e));
+        info!("Import {} edges from {}", edges.len(), f);
+        parmesan_info.edges.extend(edges);
+    }
+    let mut cfg = ControlFlowGraph::new(parmesan_info);
+    cfg.set_score_strategy(parse_score_strategy(score_strategy));
+    if resuming {
+        resume::load_cfg(&angora_out_dir, &mut cfg).expect("Could not load saved cfg state");
+    }
+    for f in &sync_opt.import_cfgs {
+        let state = resume::read_cfg_state(Path::new(f))
+            .unwrap_or_else(|e| panic!("Could not import the cfg {:?}: {:?}", f, e));
+        info!(
+            "Import cfg {}: {} edges, {} solved targets",
+            f,
+            state.edges.len(),
+            state.solved_targets.len()
+        );
+        cfg.load_state(state);
+    }
 
-    let command_option = command::CommandOpt::new(
+    let mut command_option = command::CommandOpt::new(
         mode,
         track_target,
         pargs,
@@ -60,16 +118,21 @@ pub fn fuzz_main(
         cfg_input_file,
         sanopt_target,
         directed_only,
+        shm_input,
     );
+    // Like AFL's secondary instances, leave the deterministic stage to the main one.
+    command_option.enable_det = !sync_opt.is_secondary;
     info!("{:?}", command_option);
 
     check_dep::check_dep(in_dir, out_dir, &command_option);
 
-    let depot = Arc::new(depot::Depot::new(seeds_dir, &angora_out_dir, RwLock::new(cfg.clone())));
+    let cfg = Arc::new(RwLock::new(cfg));
+    let depot = Arc::new(depot::Depot::new(seeds_dir, &angora_out_dir, cfg.clone()));
     info!("{:?}", depot.dirs);
 
     let stats = Arc::new(RwLock::new(stats::ChartStats::new()));
-    let global_branches = Arc::new(branches::GlobalBranches::new(RwLock::new(cfg)));
+    stats.write().unwrap().set_score_strategy(cfg.read().unwrap().get_score_strategy());
+    let global_branches = Arc::new(branches::GlobalBranches::new(cfg));
     let fuzzer_stats = create_stats_file_and_write_pid(&angora_out_dir);
     let running = Arc::new(AtomicBool::new(true));
     set_sigint_handler(running.clone());
@@ -90,7 +153,31 @@ pub fn fuzz_main(
         branch_cov.clone(),
     );
 
-    depot::sync_depot(&mut executor, running.clone(), &depot.dirs.seeds_dir);
+    if resuming {
+        resume::load_state(&angora_out_dir, &depot, &global_branches)
+            .expect("Could not load saved fuzzing state");
+    } else {
+        depot::sync_depot(&mut executor, running.clone(), &depot.dirs.seeds_dir);
+    }
+
+    if command_option.auto_time_limit {
+        if resuming {
+            // No dry run when resuming, keep the limit calibrated by the first run.
+            match resume::read_time_limit(&angora_out_dir) {
+                Ok(time_limit) => {
+                    info!("Resume time limit: {} ms", time_limit);
+                    command_option.set_time_limit(time_limit);
+                },
+                Err(e) => {
+                    warn!("No saved time limit ({:?}), use the default one", e);
+                    command_option.set_time_limit(config::TIME_LIMIT);
+                },
+            }
+        } else {
+            command_option.calibrate_time_limit(executor.slowest_exec_us);
+        }
+        executor.set_time_limit(command_option.time_limit);
+    }
 
     if depot.empty() {
         error!("Failed to find any branches during dry run.");
@@ -116,7 +203,13 @@ pub fn fuzz_main(
         &branch_cov,
     );
 
-    let log_file = match fs::File::create(angora_out_dir.join(defs::ANGORA_LOG_FILE)) {
+    let log_file = match fs::OpenOptions::new()
+        .create(true)
+        .write(true)
+        .append(resuming)
+        .truncate(!resuming)
+        .open(angora_out_dir.join(defs::ANGORA_LOG_FILE))
+    {
         Ok(a) => a,
         Err(e) => {
             error!("FATAL: Could not create log file: {:?}", e);
@@ -127,7 +220,8 @@ pub fn fuzz_main(
     main_thread_sync_and_log(
         log_file,
         out_dir,
-        sync_afl,
+        &angora_out_dir,
+        &sync_opt,
         running.clone(),
         &mut executor,
         &depot,
@@ -142,6 +236,16 @@ pub fn fuzz_main(
         }
     }
 
+    resume::save_state(&angora_out_dir, &depot, &global_branches, executor.cmd.time_limit);
+    let learned_cfg = angora_out_dir.join(defs::LEARNED_CFG_FILE);
+    if let Err(e) = resume::write_cfg_state(&learned_cfg, &depot.cfg.read().unwrap().get_state()) {
+        warn!("Could not write {:?}: {:?}", learned_cfg, e);
+    }
+    export_cfg(&angora_out_dir, &depot);
+    if let Err(e) = depot.targets.save(&angora_out_dir) {
+        warn!("Could not write the target progress: {:?}", e);
+    }
+
     match fs::remove_file(&fuzzer_stats) {
         Ok(_) => (),
         Err(e) => warn!("Could not remove fuzzer stats file: {:?}", e),
@@ -192,11 +296,14 @@ pub fn fuzz_main(
     }
 }
 
-fn initialize_directories(in_dir: &str, out_dir: &str, sync_afl: bool) -> (PathBuf, PathBuf) {
-    let angora_out_dir = if sync_afl {
-        gen_path_afl(out_dir)
-    } else {
-        PathBuf::from(out_dir)
+fn initialize_directories(in_dir: &str, out_dir: &str, dir_name: Option<&str>) -> (PathBuf, PathBuf) {
+    if in_dir == "-" {
+        return resume_directories(out_dir, dir_name);
+    }
+
+    let angora_out_dir = match dir_name {
+        Some(name) => gen_path_afl(out_dir, name),
+        None => PathBuf::from(out_dir),
     };
 
     match fs::create_dir(&angora_out_dir) {
@@ -235,13 +342,32 @@ fn initialize_directories(in_dir: &str, out_dir: &str, sync_afl: bool) -> (PathB
     (seeds_dir, angora_out_dir)
 }
 
-fn gen_path_afl(out_dir: &str) -> PathBuf {
+// Resume with "-i -": keep everything in the output directory.
+fn resume_directories(out_dir: &str, dir_name: Option<&str>) -> (PathBuf, PathBuf) {
+    let angora_out_dir = match dir_name {
+        Some(name) => PathBuf::from(out_dir).join(name),
+        None => PathBuf::from(out_dir),
+    };
+
+    if !resume::has_state(&angora_out_dir) {
+        panic!(
+            "Can not resume: no saved state in {:?}. Start a new campaign with a seed directory instead.",
+            angora_out_dir
+        );
+    }
+    info!("Resume fuzzing from {:?}", angora_out_dir);
+
+    let seeds_dir = angora_out_dir.join(defs::INPUTS_DIR);
+    (seeds_dir, angora_out_dir)
+}
+
+fn gen_path_afl(out_dir: &str, dir_name: &str) -> PathBuf {
     let base_path = PathBuf::from(out_dir);
     let create_dir_result = fs::create_dir(&base_path);
     if create_dir_result.is_err() {
         warn!("dir has existed. {:?}", base_path);
     }
-    base_path.join(defs::ANGORA_DIR_NAME)
+    base_path.join(dir_name)
 }
 
 fn get_func_num(s : Option<&str>) -> usize {
@@ -315,10 +441,19 @@ fn init_cpus_and_run_fuzzing_threads(
     (handlers, child_count)
 }
 
+// Only hold the lock to take the export, not to write the files.
+fn export_cfg(angora_out_dir: &Path, depot: &depot::Depot) {
+    let export = depot.cfg.read().unwrap().export();
+    if let Err(e) = write_cfg_export(angora_out_dir, &export) {
+        warn!("Could not export the cfg: {:?}", e);
+    }
+}
+
 fn main_thread_sync_and_log(
     mut log_file: fs::File,
     out_dir: &str,
-    sync_afl: bool,
+    angora_out_dir: &Path,
+    sync_opt: &SyncOpt,
     running: Arc<AtomicBool>,
     executor: &mut executor::Executor,
     depot: &Arc<depot::Depot>,
@@ -328,19 +463,51 @@ fn main_thread_sync_and_log(
 ) {
     let mut last_explore_num = stats.read().unwrap().get_explore_num();
     let sync_dir = Path::new(out_dir);
-    let mut synced_ids = HashMap::new();
-    if sync_afl {
-        depot::sync_afl(executor, running.clone(), sync_dir, &mut synced_ids);
-    }
+    let other_sources = || {
+        let mut sources = match sync_opt.dir_name() {
+            Some(name) => depot::afl_sources(sync_dir, name),
+            None => vec![],
+        };
+        sources.extend(sync_opt.dirs.iter().map(|d| (d.clone(), PathBuf::from(d))));
+        sources
+    };
+    let inbox = (defs::INBOX_DIR.to_string(), depot.dirs.inbox_dir.clone());
+    let sync_stats_file = angora_out_dir.join(defs::SYNC_STATS_FILE);
+    let mut sync_state = depot::SyncState::default();
+    let mut sync_client = sync_opt.server.as_ref().map(|addr| {
+        netsync::SyncClient::new(addr, sync_opt.instance.as_deref().unwrap_or("parmesan"))
+    });
+    depot::sync_sources(executor, running.clone(), &other_sources(), &mut sync_state);
+    sync_state.save(&sync_stats_file);
     let mut sync_counter = 1;
+    let mut last_save = time::Instant::now();
     show_stats(&mut log_file, depot, global_branches, stats);
     while running.load(Ordering::SeqCst) {
         thread::sleep(time::Duration::from_secs(5));
         sync_counter -= 1;
-        if sync_afl && sync_counter <= 0 {
-            depot::sync_afl(executor, running.clone(), sync_dir, &mut synced_ids);
+        let mut sources = vec![inbox.clone()];
+        if sync_counter <= 0 {
+            sources.extend(other_sources());
+            if let Some(name) = sync_opt.dir_name() {
+                depot::sync_cfgs(&depot.cfg, sync_dir, name, &mut sync_state);
+                if sync_opt.sync_conds {
+                    depot::sync_cond_queues(depot, sync_dir, name, &mut sync_state);
+                }
+            }
+            if let Some(client) = sync_client.as_mut() {
+                netsync::sync_server(client, executor, depot, running.clone());
+            }
             sync_counter = 12;
         }
+        if depot::sync_sources(executor, running.clone(), &sources, &mut sync_state) > 0 {
+            sync_state.save(&sync_stats_file);
+        }
+
+        if last_save.elapsed() >= time::Duration::from_secs(config::SAVE_STATE_INTERVAL) {
+            resume::save_state(angora_out_dir, depot, global_branches, executor.cmd.time_limit);
+            export_cfg(angora_out_dir, depot);
+            last_save = time::Instant::now();
+        }
 
         show_stats(&mut log_file, depot, global_branches, stats);
         if Arc::strong_count(&child_count) == 1 {
@@ -0,0 +1,101 @@
+// Explain one condition on an input: what tracking found about it,
+// and its objective value on the fast program.
+use crate::{
+    command,
+    cond_stmt::CondStmt,
+    depot::read_from_file,
+    executor::Runner,
+};
+use angora_common::defs;
+use std::path::Path;
+
+fn print_cond(cond: &CondStmt) {
+    let base = &cond.base;
+    println!(
+        "cmpid {}, context {}, order {}, condition {}",
+        base.cmpid, base.context, base.order, base.condition
+    );
+    println!(
+        "  op 0x{:x}, size {}, arg1 0x{:x}, arg2 0x{:x}, explore {}, exploitable {}",
+        base.op,
+        base.size,
+        base.arg1,
+        base.arg2,
+        base.is_explore(),
+        base.is_exploitable()
+    );
+    println!("  offsets: {:?}", cond.offsets);
+    println!("  offsets_opt: {:?}", cond.offsets_opt);
+    println!("  variables: {:?}", cond.variables);
+}
+
+// CMPID[:CONTEXT[:ORDER]], any context or order if not given.
+fn parse_cond(s: &str) -> (u32, Option<u32>, Option<u32>) {
+    let fields: Vec<u32> = s
+        .split(':')
+        .map(|v| v.parse().unwrap_or_else(|_| panic!("Invalid condition {:?}", s)))
+        .collect();
+    if fields.len() > 3 {
+        panic!("Invalid condition {:?}, use CMPID[:CONTEXT[:ORDER]]", s);
+    }
+    (fields[0], fields.get(1).cloned(), fields.get(2).cloned())
+}
+
+fn matches(cond: &CondStmt, cmpid: u32, context: Option<u32>, order: Option<u32>) -> bool {
+    cond.base.cmpid == cmpid
+        && context.iter().all(|&c| cond.base.context == c)
+        && order.iter().all(|&o| cond.base.order & 0xFFFF == o)
+}
+
+pub fn inspect_main(
+    in_file: &str,
+    track_target: &str,
+    pargs: Vec<String>,
+    mem_limit: u64,
+    time_limit: Option<&str>,
+    cond: &str,
+) {
+    pretty_env_logger::init();
+
+    let (cmpid, context, order) = parse_cond(cond);
+    let time_limit = command::parse_tool_time_limit(time_limit);
+    let buf = read_from_file(Path::new(in_file));
+    let mut runner = Runner::new(pargs, time_limit, mem_limit);
+
+    let cond_list = runner
+        .track(track_target, &buf)
+        .unwrap_or_else(|e| panic!("Could not track the input: {}", e));
+    let conds: Vec<&CondStmt> = cond_list
+        .iter()
+        .filter(|c| matches(c, cmpid, context, order))
+        .collect();
+    if conds.is_empty() {
+        println!(
+            "cmpid {} is not in the {} conditions tracked on the input: not reached, or filtered out (e.g. not tainted by the input)",
+            cmpid,
+            cond_list.len()
+        );
+        return;
+    }
+
+    for cond in conds {
+        print_cond(cond);
+        let (status, output) = runner.run_with_cond(&buf, cond);
+        if output == defs::UNREACHABLE {
+            println!("  output: unreachable on the fast program ({:?})", status);
+        } else {
+            println!("  output: {} ({:?}), 0 means the constraint is solved", output, status);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_cond_fields() {
+        assert_eq!(parse_cond("12"), (12, None, None));
+        assert_eq!(parse_cond("12:34:1"), (12, Some(34), Some(1)));
+    }
+}
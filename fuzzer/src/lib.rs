@@ -23,16 +23,27 @@ mod search;
 mod stats;
 pub mod track;
 
+mod cmin;
 mod fuzz_loop;
 mod fuzz_main;
 mod fuzz_type;
+mod inspect;
+mod replay;
+mod showmap;
+mod tmin;
 
 mod bind_cpu;
 mod check_dep;
 mod command;
 mod tmpfs;
+mod resume;
+mod triage;
 
 mod dyncfg;
+mod netsync;
 //mod directed;
 
-pub use crate::fuzz_main::fuzz_main;
+pub use crate::{
+    cmin::cmin_main, fuzz_main::{fuzz_main, SyncOpt}, inspect::inspect_main, netsync::server_main, replay::replay_main,
+    showmap::showmap_main, tmin::tmin_main,
+};
@@ -0,0 +1,236 @@
+// An instance's side of the sync server: push the new inputs and what the cfg
+// learned here, and import the ones of the other instances.
+use super::protocol::*;
+use crate::{depot::Depot, executor::Executor};
+use angora_common::config;
+use std::{
+    io,
+    net::{TcpStream, ToSocketAddrs},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+const SYNC_TIMEOUT: Duration = Duration::from_secs(30);
+
+pub struct SyncClient {
+    addr: String,
+    // Unique for each instance, the server doesn't send us back what we pushed.
+    name: String,
+    stream: Option<TcpStream>,
+    since: usize,
+    // The id of the first input in the queue that is not pushed yet.
+    next_input: usize,
+    known: Known,
+    // Not sent because the server was not reachable.
+    pending: Option<Update>,
+}
+
+impl SyncClient {
+    pub fn new(addr: &str, name: &str) -> Self {
+        Self {
+            addr: addr.to_string(),
+            name: format!("{}-{:08x}", name, rand::random::<u32>()),
+            stream: None,
+            since: 0,
+            next_input: 0,
+            known: Known::default(),
+            pending: None,
+        }
+    }
+
+    fn connect(&self) -> io::Result<TcpStream> {
+        let addr = self
+            .addr
+            .to_socket_addrs()?
+            .next()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "no address"))?;
+        let stream = TcpStream::connect_timeout(&addr, SYNC_TIMEOUT)?;
+        stream.set_read_timeout(Some(SYNC_TIMEOUT))?;
+        stream.set_write_timeout(Some(SYNC_TIMEOUT))?;
+        Ok(stream)
+    }
+
+    fn request(&mut self, req: &Request) -> io::Result<Response> {
+        let mut stream = match self.stream.take() {
+            Some(stream) => stream,
+            None => self.connect()?,
+        };
+        write_message(&mut stream, req)?;
+        let resp = read_message::<Response>(&mut stream)?;
+        self.stream = Some(stream);
+        Ok(resp)
+    }
+
+    // Send our update and get the others', page by page.
+    // If it can't be sent, reconnect and send it again next time.
+    pub fn exchange(&mut self, mut update: Update) -> io::Result<Update> {
+        if let Some(pending) = self.pending.take() {
+            update.extend(&pending);
+        }
+        let mut got = Update::default();
+        let mut pushed = false;
+        loop {
+            let req = Request {
+                name: self.name.clone(),
+                since: self.since,
+                update,
+            };
+            match self.request(&req) {
+                Ok(resp) => {
+                    self.since = resp.next;
+                    got.extend(&resp.update);
+                    if !resp.more {
+                        return Ok(got);
+                    }
+                    pushed = true;
+                    update = Update::default();
+                },
+                // The rest of the pages is pulled next time.
+                Err(e) if pushed => {
+                    warn!("Could not get all the updates from the server: {:?}", e);
+                    return Ok(got);
+                },
+                Err(e) => {
+                    self.pending = Some(req.update);
+                    return Err(e);
+                },
+            }
+        }
+    }
+
+    fn local_update(&mut self, depot: &Depot) -> Update {
+        // One page of inputs at a time, and none until the pending ones are sent.
+        let num_inputs = depot.num_inputs.load(Ordering::Relaxed);
+        let mut inputs = vec![];
+        let mut len = 0;
+        while self.pending.is_none() && self.next_input < num_inputs && len < SYNC_PAGE_LEN {
+            let buf = depot.get_input_buf(self.next_input);
+            self.next_input += 1;
+            if buf.len() < config::MAX_INPUT_LEN {
+                len += buf.len();
+                inputs.push(buf);
+            }
+        }
+
+        let mut update = Update {
+            inputs,
+            cfg: depot.cfg.read().unwrap().get_state(),
+        };
+        self.known.filter(&mut update);
+        update
+    }
+}
+
+pub fn sync_server(client: &mut SyncClient, executor: &mut Executor, depot: &Depot, running: Arc<AtomicBool>) {
+    let local = client.local_update(depot);
+    let mut update = match client.exchange(local) {
+        Ok(update) => update,
+        Err(e) => {
+            warn!("Could not sync with the server {}: {:?}", client.addr, e);
+            return;
+        },
+    };
+    // Don't push them back.
+    client.known.filter(&mut update);
+    if update.is_empty() {
+        return;
+    }
+
+    info!(
+        "sync {} inputs, {} solved targets and {} edges from the server.",
+        update.inputs.len(),
+        update.cfg.solved_targets.len(),
+        update.cfg.edges.len()
+    );
+
+    depot.cfg.write().unwrap().load_state(update.cfg);
+
+    executor.rebind_forksrv();
+    executor.local_stats.clear();
+    for buf in &update.inputs {
+        if !running.load(Ordering::SeqCst) {
+            break;
+        }
+        executor.run_sync(buf);
+    }
+    executor.update_log();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        dyncfg::cfg::CfgState,
+        netsync::server::{serve, Coordinator},
+    };
+    use std::{
+        net::TcpListener,
+        sync::Mutex,
+        thread,
+    };
+
+    #[test]
+    fn exchange_on_loopback() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        thread::spawn(move || serve(listener, Arc::new(Mutex::new(Coordinator::default()))));
+
+        let mut a = SyncClient::new(&addr, "a");
+        let mut b = SyncClient::new(&addr, "b");
+        let found = Update {
+            inputs: vec![b"AB".to_vec()],
+            cfg: CfgState {
+                solved_targets: vec![3],
+                edges: vec![(1, 2)],
+                indirect_edges: vec![(7, (1, 2))],
+                magic_bytes: vec![((1, 2), vec![(0, b'A')])],
+                edge_hits: vec![((1, 2), 5)],
+            },
+        };
+        assert!(a.exchange(found).unwrap().is_empty());
+        let found = Update {
+            inputs: vec![b"AB".to_vec(), b"CD".to_vec()],
+            ..Default::default()
+        };
+        let got = b.exchange(found).unwrap();
+        assert_eq!(got.inputs, vec![b"AB".to_vec()]);
+        assert_eq!(got.cfg.solved_targets, vec![3]);
+        assert_eq!(got.cfg.edges, vec![(1, 2)]);
+        assert_eq!(got.cfg.magic_bytes, vec![((1, 2), vec![(0, b'A')])]);
+        assert!(got.cfg.edge_hits.is_empty());
+        // AB was pushed by a already.
+        assert_eq!(a.exchange(Update::default()).unwrap().inputs, vec![b"CD".to_vec()]);
+        assert!(b.exchange(Update::default()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn retry_and_pages() {
+        // Nothing listens on the port yet.
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        drop(listener);
+
+        let mut a = SyncClient::new(&addr, "a");
+        let mut b = SyncClient::new(&addr, "b");
+        let found = Update {
+            inputs: vec![b"AB".to_vec(), b"CD".to_vec(), b"EF".to_vec()],
+            cfg: CfgState {
+                edges: vec![(1, 2)],
+                ..Default::default()
+            },
+        };
+        assert!(a.exchange(found).is_err());
+
+        let listener = TcpListener::bind(&addr).unwrap();
+        // One input per page.
+        thread::spawn(move || serve(listener, Arc::new(Mutex::new(Coordinator::new(1)))));
+        assert!(a.exchange(Update::default()).unwrap().is_empty());
+        let got = b.exchange(Update::default()).unwrap();
+        assert_eq!(got.inputs, vec![b"AB".to_vec(), b"CD".to_vec(), b"EF".to_vec()]);
+        assert_eq!(got.cfg.edges, vec![(1, 2)]);
+        assert!(b.exchange(Update::default()).unwrap().is_empty());
+    }
+}
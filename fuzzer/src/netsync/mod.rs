@@ -0,0 +1,8 @@
+mod client;
+mod protocol;
+mod server;
+
+pub use self::{
+    client::{sync_server, SyncClient},
+    server::server_main,
+};
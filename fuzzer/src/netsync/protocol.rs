@@ -0,0 +1,109 @@
+// Messages between the instances and the sync server: a big endian u32 length,
+// then the message in JSON.
+use crate::dyncfg::cfg::{CallSiteId, CfgState, CmpId, Edge, FixedBytes};
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use serde::{de::DeserializeOwned, Serialize};
+use std::{
+    collections::{hash_map::DefaultHasher, HashSet},
+    hash::{Hash, Hasher},
+    io::{self, prelude::*},
+};
+
+// Larger messages are refused, the inputs are limited by MAX_INPUT_LEN.
+const MAX_MESSAGE_LEN: usize = 1 << 30;
+// The bytes of inputs in one message. The rest is sent in the next ones,
+// so that a large queue stays far below MAX_MESSAGE_LEN in JSON.
+pub const SYNC_PAGE_LEN: usize = 1 << 24;
+
+#[derive(Serialize, Deserialize, Default, Debug)]
+pub struct Update {
+    pub inputs: Vec<Vec<u8>>,
+    // Solved targets, edges, indirect-call edges and magic bytes.
+    pub cfg: CfgState,
+}
+
+impl Update {
+    pub fn is_empty(&self) -> bool {
+        self.inputs.is_empty()
+            && self.cfg.solved_targets.is_empty()
+            && self.cfg.edges.is_empty()
+            && self.cfg.indirect_edges.is_empty()
+            && self.cfg.magic_bytes.is_empty()
+    }
+
+    pub fn extend(&mut self, other: &Update) {
+        self.inputs.extend(other.inputs.iter().cloned());
+        self.cfg.solved_targets.extend(other.cfg.solved_targets.iter().cloned());
+        self.cfg.edges.extend(other.cfg.edges.iter().cloned());
+        self.cfg.indirect_edges.extend(other.cfg.indirect_edges.iter().cloned());
+        self.cfg.magic_bytes.extend(other.cfg.magic_bytes.iter().cloned());
+    }
+}
+
+fn input_hash(buf: &[u8]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    buf.hash(&mut hasher);
+    hasher.finish()
+}
+
+fn retain_new<T: Eq + Hash + Clone>(items: &mut Vec<T>, known: &mut HashSet<T>) {
+    items.retain(|item| known.insert(item.clone()));
+}
+
+// What was already sent or received.
+#[derive(Default)]
+pub struct Known {
+    input_hashes: HashSet<u64>,
+    solved_targets: HashSet<CmpId>,
+    edges: HashSet<Edge>,
+    indirect_edges: HashSet<(CallSiteId, Edge)>,
+    magic_bytes: HashSet<(Edge, FixedBytes)>,
+}
+
+impl Known {
+    // Remove what is known from the update, and remember the rest.
+    pub fn filter(&mut self, update: &mut Update) {
+        let input_hashes = &mut self.input_hashes;
+        update.inputs.retain(|buf| input_hashes.insert(input_hash(buf)));
+        retain_new(&mut update.cfg.solved_targets, &mut self.solved_targets);
+        retain_new(&mut update.cfg.edges, &mut self.edges);
+        retain_new(&mut update.cfg.indirect_edges, &mut self.indirect_edges);
+        retain_new(&mut update.cfg.magic_bytes, &mut self.magic_bytes);
+        // The hits only weight the scores of the instance that ran the inputs.
+        update.cfg.edge_hits.clear();
+    }
+}
+
+// Push what the instance found, and pull what the others found since `since`.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Request {
+    pub name: String,
+    pub since: usize,
+    pub update: Update,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Response {
+    // `since` of the next request.
+    pub next: usize,
+    // The update is one page, ask again for the rest.
+    pub more: bool,
+    pub update: Update,
+}
+
+pub fn write_message<T: Serialize>(w: &mut dyn Write, msg: &T) -> io::Result<()> {
+    let buf = serde_json::to_vec(msg)?;
+    w.write_u32::<BigEndian>(buf.len() as u32)?;
+    w.write_all(&buf)?;
+    w.flush()
+}
+
+pub fn read_message<T: DeserializeOwned>(r: &mut dyn Read) -> io::Result<T> {
+    let len = r.read_u32::<BigEndian>()? as usize;
+    if len > MAX_MESSAGE_LEN {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "sync message too long"));
+    }
+    let mut buf = vec![0; len];
+    r.read_exact(&mut buf)?;
+    Ok(serde_json::from_slice(&buf)?)
+}
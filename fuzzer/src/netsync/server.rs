@@ -0,0 +1,111 @@
+// The sync server: it keeps everything the instances pushed, and sends each one
+// what the others pushed since its last request.
+use super::protocol::*;
+use std::{
+    io,
+    net::{TcpListener, TcpStream},
+    sync::{Arc, Mutex},
+    thread,
+};
+
+pub struct Coordinator {
+    // The new part of each update, in order, with the name of the instance.
+    log: Vec<(String, Update)>,
+    known: Known,
+    // The bytes of inputs in a response.
+    page_len: usize,
+}
+
+impl Default for Coordinator {
+    fn default() -> Self {
+        Self::new(SYNC_PAGE_LEN)
+    }
+}
+
+impl Coordinator {
+    pub fn new(page_len: usize) -> Self {
+        Self {
+            log: vec![],
+            known: Known::default(),
+            page_len,
+        }
+    }
+
+    pub fn handle(&mut self, req: Request) -> Response {
+        // The server was restarted since the last request, send everything again.
+        let since = if req.since > self.log.len() { 0 } else { req.since };
+
+        let mut update = Update::default();
+        let mut len = 0;
+        let mut next = since;
+        for (name, u) in &self.log[since..] {
+            if len >= self.page_len {
+                break;
+            }
+            next += 1;
+            if *name != req.name {
+                len += u.inputs.iter().map(|buf| buf.len()).sum::<usize>();
+                update.extend(u);
+            }
+        }
+        let more = next < self.log.len();
+
+        let mut new = req.update;
+        self.known.filter(&mut new);
+        if !new.is_empty() {
+            self.log.push((req.name, new));
+        }
+
+        // Skip what was just pushed, unless there is more to send before it.
+        Response {
+            next: if more { next } else { self.log.len() },
+            more,
+            update,
+        }
+    }
+}
+
+fn handle_client(mut stream: TcpStream, coordinator: Arc<Mutex<Coordinator>>) -> io::Result<()> {
+    loop {
+        let req: Request = match read_message(&mut stream) {
+            Ok(req) => req,
+            Err(ref e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(()),
+            Err(e) => return Err(e),
+        };
+        let name = req.name.clone();
+        let num_pushed = req.update.inputs.len();
+        let resp = coordinator.lock().unwrap().handle(req);
+        debug!(
+            "sync {}: got {} inputs, sent {} inputs",
+            name,
+            num_pushed,
+            resp.update.inputs.len()
+        );
+        write_message(&mut stream, &resp)?;
+    }
+}
+
+pub fn serve(listener: TcpListener, coordinator: Arc<Mutex<Coordinator>>) {
+    for stream in listener.incoming() {
+        match stream {
+            Ok(stream) => {
+                let coordinator = coordinator.clone();
+                thread::spawn(move || {
+                    let peer = stream.peer_addr().ok();
+                    if let Err(e) = handle_client(stream, coordinator) {
+                        warn!("Sync with {:?} failed: {:?}", peer, e);
+                    }
+                });
+            },
+            Err(e) => warn!("Could not accept a connection: {:?}", e),
+        }
+    }
+}
+
+pub fn server_main(addr: &str) {
+    pretty_env_logger::init();
+
+    let listener = TcpListener::bind(addr).unwrap_or_else(|e| panic!("Could not listen on {}: {:?}", addr, e));
+    info!("Sync server listening on {:?}", listener.local_addr());
+    serve(listener, Arc::new(Mutex::new(Coordinator::default())));
+}
@@ -0,0 +1,229 @@
+// Replay the queue and the crashes of a campaign on a new build of the program,
+// and report what changed instead of fuzzing.
+// The new output directory holds what the replay kept, and can be fuzzed with `-i -`.
+use crate::{
+    branches, check_dep, command,
+    depot::{self, list_files, read_from_file},
+    dyncfg::{
+        cfg::{CmpId, ControlFlowGraph},
+        fparse::parse_targets_file,
+    },
+    executor::{Executor, StatusType},
+    resume, stats,
+    triage::CrashReport,
+};
+use angora_common::defs;
+use std::{
+    collections::BTreeSet,
+    fmt::Write as FmtWrite,
+    fs,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, RwLock},
+};
+
+static REPLAY_REPORT_FILE: &str = "replay_report.txt";
+// The edges and targets listed in the report, the rest are only counted.
+const REPLAY_REPORT_MAX_LIST: usize = 50;
+
+fn format_list<T: std::fmt::Debug>(items: &[T]) -> String {
+    if items.len() > REPLAY_REPORT_MAX_LIST {
+        format!("{:?} ...", &items[..REPLAY_REPORT_MAX_LIST])
+    } else {
+        format!("{:?}", items)
+    }
+}
+
+// The angora directory of the old campaign, which may be in AFL's directory structure.
+fn old_angora_dir(old_dir: &str) -> PathBuf {
+    let dir = PathBuf::from(old_dir);
+    let angora_dir = dir.join(defs::ANGORA_DIR_NAME);
+    if !dir.join(defs::INPUTS_DIR).is_dir() && angora_dir.is_dir() {
+        return angora_dir;
+    }
+    dir
+}
+
+fn replay_queue(executor: &mut Executor, queue_dir: &Path, report: &mut String) -> BTreeSet<CmpId> {
+    let mut reached_targets = BTreeSet::new();
+    let (mut num_inputs, mut num_kept, mut num_crashes, mut num_hangs) = (0, 0, 0, 0);
+    for path in list_files(queue_dir) {
+        let status = match depot::sync_file(executor, &path) {
+            Some(status) => status,
+            None => continue,
+        };
+        num_inputs += 1;
+        match status {
+            StatusType::Crash => num_crashes += 1,
+            StatusType::Timeout => num_hangs += 1,
+            _ => {},
+        }
+        if executor.has_new_path {
+            num_kept += 1;
+        }
+        reached_targets.extend(executor.branches.get_reached_targets());
+    }
+    writeln!(
+        report,
+        "queue: {} inputs, {} with new paths on the new build, {} crash, {} hang",
+        num_inputs, num_kept, num_crashes, num_hangs
+    )
+    .unwrap();
+    reached_targets
+}
+
+fn replay_crashes(executor: &mut Executor, crashes_dir: &Path, report: &mut String) {
+    let mut lines = String::new();
+    let (mut num_crashes, mut num_same, mut num_changed, mut num_gone) = (0, 0, 0, 0);
+    for path in list_files(crashes_dir) {
+        if path.extension().and_then(|ext| ext.to_str()) == Some(defs::CRASH_REPORT_EXT) {
+            continue;
+        }
+        let status = match depot::sync_file(executor, &path) {
+            Some(status) => status,
+            None => continue,
+        };
+        num_crashes += 1;
+        let name = path.file_name().unwrap().to_string_lossy();
+        if status != StatusType::Crash {
+            num_gone += 1;
+            writeln!(lines, "  {}: not reproduced ({:?})", name, status).unwrap();
+            continue;
+        }
+        let old_sig = CrashReport::load(&path.with_extension(defs::CRASH_REPORT_EXT))
+            .ok()
+            .and_then(|r| r.signature());
+        let new_report = executor.reproduce_crash(&read_from_file(&path));
+        let bug_type = new_report.bug_type.as_deref().unwrap_or("unknown");
+        match (old_sig, new_report.signature()) {
+            (Some(old), Some(new)) if old != new => {
+                num_changed += 1;
+                writeln!(lines, "  {}: reproduced, different bug or stack ({})", name, bug_type).unwrap();
+            },
+            _ => {
+                num_same += 1;
+                writeln!(lines, "  {}: reproduced ({})", name, bug_type).unwrap();
+            },
+        }
+    }
+    writeln!(
+        report,
+        "crashes: {} replayed, {} reproduced, {} reproduced as a different bug, {} not reproduced",
+        num_crashes, num_same, num_changed, num_gone
+    )
+    .unwrap();
+    report.push_str(&lines);
+}
+
+// Edge ids are only comparable if the cmpids are kept stable between the builds,
+// e.g. with tools/llvm-diff-parmesan.
+fn diff_edges(old_dir: &Path, global_branches: &branches::GlobalBranches, report: &mut String) {
+    let new_edges: BTreeSet<usize> = global_branches.get_covered_edges().into_iter().collect();
+    match resume::read_state_file(old_dir, defs::VIRGIN_BRANCHES_FILE) {
+        Ok(virgin) => {
+            let old_edges: BTreeSet<usize> = branches::covered_edges(&virgin).into_iter().collect();
+            let gained: Vec<&usize> = new_edges.difference(&old_edges).collect();
+            let lost: Vec<&usize> = old_edges.difference(&new_edges).collect();
+            writeln!(
+                report,
+                "edges: {} on the old build, {} on the new build, {} gained, {} lost",
+                old_edges.len(),
+                new_edges.len(),
+                gained.len(),
+                lost.len()
+            )
+            .unwrap();
+            writeln!(report, "  gained: {}", format_list(&gained)).unwrap();
+            writeln!(report, "  lost: {}", format_list(&lost)).unwrap();
+        },
+        Err(e) => {
+            warn!("No coverage of the old campaign: {:?}", e);
+            writeln!(
+                report,
+                "edges: {} on the new build, the old campaign has no saved state to compare",
+                new_edges.len()
+            )
+            .unwrap();
+        },
+    }
+}
+
+pub fn replay_main(
+    old_dir: &str,
+    out_dir: &str,
+    track_target: &str,
+    pargs: Vec<String>,
+    mem_limit: u64,
+    time_limit: Option<&str>,
+    cfg_file: &str,
+) {
+    pretty_env_logger::init();
+
+    let old_dir = old_angora_dir(old_dir);
+    let queue_dir = old_dir.join(defs::INPUTS_DIR);
+    let crashes_dir = old_dir.join(defs::CRASHES_DIR);
+    let out_dir = PathBuf::from(out_dir);
+    if out_dir.exists() {
+        panic!("The output directory {:?} already exists", out_dir);
+    }
+    fs::create_dir(&out_dir).expect("Could not create the output directory");
+
+    let command_option = command::CommandOpt::new(
+        "llvm",
+        track_target,
+        pargs,
+        &out_dir,
+        "gd",
+        mem_limit,
+        time_limit,
+        true,
+        true,
+        cfg_file,
+        None,
+        false,
+        false,
+    );
+    check_dep::check_dep(queue_dir.to_str().unwrap(), out_dir.to_str().unwrap(), &command_option);
+
+    let parmesan_info = parse_targets_file(Path::new(cfg_file)).expect("Could not read cfg targets file");
+    let targets: BTreeSet<CmpId> = parmesan_info.targets.iter().cloned().collect();
+    let cfg = Arc::new(RwLock::new(ControlFlowGraph::new(parmesan_info)));
+    let depot = Arc::new(depot::Depot::new(queue_dir.clone(), &out_dir, cfg.clone()));
+    let global_branches = Arc::new(branches::GlobalBranches::new(cfg));
+    let stats = Arc::new(RwLock::new(stats::ChartStats::new()));
+    let func_rel_map = Arc::new(RwLock::new(vec![].into_boxed_slice()));
+    let branch_cov = Arc::new(Mutex::new(vec![]));
+    let mut executor = Executor::new(
+        command_option.specify(0),
+        global_branches.clone(),
+        depot.clone(),
+        stats,
+        func_rel_map,
+        branch_cov,
+    );
+
+    let mut report = String::new();
+    writeln!(report, "replay of {:?}", old_dir).unwrap();
+    let reached = replay_queue(&mut executor, &queue_dir, &mut report);
+    diff_edges(&old_dir, &global_branches, &mut report);
+    replay_crashes(&mut executor, &crashes_dir, &mut report);
+
+    let missed: Vec<&CmpId> = targets.difference(&reached).collect();
+    let reached: Vec<&CmpId> = reached.iter().collect();
+    writeln!(
+        report,
+        "targets: {} of {} reached by the queue",
+        reached.len(),
+        targets.len()
+    )
+    .unwrap();
+    writeln!(report, "  reached: {}", format_list(&reached)).unwrap();
+    writeln!(report, "  not reached: {}", format_list(&missed)).unwrap();
+
+    resume::save_state(&out_dir, &depot, &global_branches, executor.cmd.time_limit);
+
+    print!("{}", report);
+    let report_path = out_dir.join(REPLAY_REPORT_FILE);
+    if let Err(e) = fs::write(&report_path, &report) {
+        warn!("Could not write {:?}: {:?}", report_path, e);
+    }
+}
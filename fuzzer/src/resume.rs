@@ -0,0 +1,126 @@
+// Save and restore the fuzzing state in the output directory,
+// so an interrupted campaign can be continued with `-i -`.
+
+use crate::{
+    branches::GlobalBranches,
+    depot::Depot,
+    dyncfg::cfg::{CfgState, ControlFlowGraph},
+};
+use angora_common::defs;
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+fn tmp_state_dir(out_dir: &Path) -> PathBuf {
+    out_dir.join(format!("{}.tmp", defs::STATE_DIR))
+}
+
+fn is_complete(dir: &Path) -> bool {
+    dir.join(defs::STATE_DONE_FILE).is_file()
+}
+
+// If we were interrupted while replacing the old state, the new one is still in the tmp dir.
+// A tmp dir we were interrupted while writing is ignored.
+fn find_state_dir(out_dir: &Path) -> io::Result<PathBuf> {
+    let dir = out_dir.join(defs::STATE_DIR);
+    if is_complete(&dir) {
+        return Ok(dir);
+    }
+    let tmp_dir = tmp_state_dir(out_dir);
+    if is_complete(&tmp_dir) {
+        return Ok(tmp_dir);
+    }
+    Err(io::Error::new(
+        io::ErrorKind::NotFound,
+        format!("no saved state in {:?}", out_dir),
+    ))
+}
+
+pub fn has_state(out_dir: &Path) -> bool {
+    find_state_dir(out_dir).is_ok()
+}
+
+// e.g. the virgin map of a previous campaign.
+pub fn read_state_file(out_dir: &Path, name: &str) -> io::Result<Vec<u8>> {
+    fs::read(find_state_dir(out_dir)?.join(name))
+}
+
+fn write_state(
+    dir: &Path,
+    depot: &Depot,
+    global_branches: &GlobalBranches,
+    time_limit: u64,
+) -> io::Result<()> {
+    if dir.exists() {
+        fs::remove_dir_all(dir)?;
+    }
+    fs::create_dir(dir)?;
+
+    depot.save_queue(&dir.join(defs::QUEUE_STATE_FILE))?;
+    global_branches.save_bitmaps(dir)?;
+
+    write_cfg_state(&dir.join(defs::CFG_STATE_FILE), &depot.cfg.read().unwrap().get_state())?;
+    depot.targets.save_state(dir)?;
+    fs::write(dir.join(defs::TIME_LIMIT_STATE_FILE), time_limit.to_string())?;
+
+    fs::File::create(dir.join(defs::STATE_DONE_FILE))?;
+    Ok(())
+}
+
+pub fn read_time_limit(out_dir: &Path) -> io::Result<u64> {
+    let buf = read_state_file(out_dir, defs::TIME_LIMIT_STATE_FILE)?;
+    String::from_utf8_lossy(&buf)
+        .trim()
+        .parse()
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+// What the cfg learned: edges, indirect-call edges, magic bytes and solved targets.
+pub fn write_cfg_state(path: &Path, state: &CfgState) -> io::Result<()> {
+    let f = io::BufWriter::new(fs::File::create(path)?);
+    serde_json::to_writer(f, state)?;
+    Ok(())
+}
+
+pub fn read_cfg_state(path: &Path) -> io::Result<CfgState> {
+    let f = io::BufReader::new(fs::File::open(path)?);
+    Ok(serde_json::from_reader(f)?)
+}
+
+pub fn save_state(out_dir: &Path, depot: &Depot, global_branches: &GlobalBranches, time_limit: u64) {
+    let tmp_dir = tmp_state_dir(out_dir);
+    let dir = out_dir.join(defs::STATE_DIR);
+    let res = write_state(&tmp_dir, depot, global_branches, time_limit).and_then(|_| {
+        if dir.exists() {
+            fs::remove_dir_all(&dir)?;
+        }
+        fs::rename(&tmp_dir, &dir)
+    });
+    match res {
+        Ok(_) => debug!("Saved fuzzing state to {:?}", dir),
+        Err(e) => warn!("Could not save fuzzing state: {:?}", e),
+    }
+}
+
+// Should be called before the cfg is handed to the depot and the global branches.
+pub fn load_cfg(out_dir: &Path, cfg: &mut ControlFlowGraph) -> io::Result<()> {
+    let dir = find_state_dir(out_dir)?;
+    let state = read_cfg_state(&dir.join(defs::CFG_STATE_FILE))?;
+    info!(
+        "Resume cfg: {} edges, {} solved targets",
+        state.edges.len(),
+        state.solved_targets.len()
+    );
+    cfg.load_state(state);
+    Ok(())
+}
+
+pub fn load_state(out_dir: &Path, depot: &Depot, global_branches: &GlobalBranches) -> io::Result<()> {
+    let dir = find_state_dir(out_dir)?;
+    let num = depot.load_queue(&dir.join(defs::QUEUE_STATE_FILE))?;
+    global_branches.load_bitmaps(&dir)?;
+    depot.targets.load_state(&dir)?;
+    info!("Resume {} conds from {:?}", num, dir);
+    Ok(())
+}
@@ -92,6 +92,12 @@ impl<'a> SearchHandler<'a> {
 
 impl<'a> Drop for SearchHandler<'a> {
     fn drop(&mut self) {
+        let local_stats = &self.executor.local_stats;
+        self.executor.depot.targets.fuzzed(
+            self.cond.base.cmpid,
+            local_stats.num_exec.into(),
+            local_stats.num_crashes > 0.into(),
+        );
         self.executor.update_log();
     }
 }
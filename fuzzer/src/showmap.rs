@@ -0,0 +1,91 @@
+// Print the branch map of inputs, and how they relate to the targets,
+// e.g. to see why a seed doesn't count as new with -D.
+use crate::{
+    command,
+    depot::{list_files, read_from_file},
+    dyncfg::{
+        cfg::{CmpId, ControlFlowGraph},
+        fparse::parse_targets_file,
+    },
+    executor::Runner,
+};
+use std::{
+    collections::BTreeSet,
+    fs,
+    io::{self, prelude::*},
+    path::{Path, PathBuf},
+};
+
+// One line per hit edge: index:bucket, then the targets reachable from it.
+// The summary line starts with '#'.
+fn write_map(
+    out: &mut dyn Write,
+    name: &str,
+    runner: &mut Runner,
+    cfg: Option<&ControlFlowGraph>,
+) -> io::Result<()> {
+    let buf = read_from_file(Path::new(name));
+    let status = runner.run(&buf);
+    let path = runner.get_path();
+
+    let mut num_directed = 0;
+    let mut reachable = BTreeSet::new();
+    let mut hit_targets = BTreeSet::new();
+    for &(idx, bucket) in &path {
+        write!(out, "{:06}:{}", idx, bucket)?;
+        if let Some(cfg) = cfg {
+            let cmp = idx as CmpId;
+            if cfg.is_target(cmp) {
+                hit_targets.insert(cmp);
+                write!(out, " target")?;
+            }
+            let targets = cfg.reachable_targets(cmp);
+            if !targets.is_empty() {
+                num_directed += 1;
+                write!(out, " -> {:?}", targets)?;
+                reachable.extend(targets);
+            }
+        }
+        writeln!(out)?;
+    }
+
+    write!(out, "# {}: {:?}, {} edges", name, status, path.len())?;
+    if cfg.is_some() {
+        write!(
+            out,
+            ", {} with a path to a target (only these count as new with -D), targets hit: {:?}, reachable: {:?}",
+            num_directed, hit_targets, reachable
+        )?;
+    }
+    writeln!(out)
+}
+
+pub fn showmap_main(
+    input: &str,
+    out_file: Option<&str>,
+    pargs: Vec<String>,
+    mem_limit: u64,
+    time_limit: Option<&str>,
+    cfg_file: Option<&str>,
+) {
+    pretty_env_logger::init();
+
+    let time_limit = command::parse_tool_time_limit(time_limit);
+    let cfg = cfg_file.map(|f| {
+        ControlFlowGraph::new(parse_targets_file(Path::new(f)).expect("Could not read cfg targets file"))
+    });
+    let inputs = if Path::new(input).is_dir() {
+        list_files(Path::new(input))
+    } else {
+        vec![PathBuf::from(input)]
+    };
+
+    let mut out: Box<dyn Write> = match out_file {
+        Some(f) => Box::new(io::BufWriter::new(fs::File::create(f).expect("Could not create the output file"))),
+        None => Box::new(io::stdout()),
+    };
+    let mut runner = Runner::new(pargs, time_limit, mem_limit);
+    for path in &inputs {
+        write_map(&mut out, path.to_str().unwrap(), &mut runner, cfg.as_ref()).expect("Could not write the map");
+    }
+}
@@ -1,8 +1,8 @@
 use super::*;
-use crate::{branches::GlobalBranches, depot::Depot};
+use crate::{branches::GlobalBranches, depot::Depot, dyncfg::score::ScoreStrategy};
 use colored::*;
 use serde_derive::Serialize;
-use std::sync::Arc;
+use std::sync::{atomic::Ordering, Arc};
 
 #[derive(Default, Serialize)]
 pub struct ChartStats {
@@ -21,7 +21,14 @@ pub struct ChartStats {
     num_inputs: Counter,
     num_hangs: Counter,
     num_crashes: Counter,
+    num_bugs: Counter,
+    num_dup_crashes: Counter,
+    num_flaky: Counter,
+    // Reached.
     num_targets: Counter,
+    targets: TargetSummary,
+    // To compare the directed performance of runs.
+    score_strategy: ScoreStrategy,
 
     fuzz: FuzzStats,
     search: SearchStats,
@@ -33,6 +40,10 @@ impl ChartStats {
         Default::default()
     }
 
+    pub fn set_score_strategy(&mut self, strategy: ScoreStrategy) {
+        self.score_strategy = strategy;
+    }
+
     pub fn sync_from_local(&mut self, local: &mut LocalStats) {
         self.track_time += local.track_time;
         self.num_rounds.count();
@@ -62,6 +73,11 @@ impl ChartStats {
         self.get_speed();
         self.iter_pq(depot);
         self.sync_from_branches(gb);
+        self.num_bugs = Counter(depot.bugs.num_unique());
+        self.num_dup_crashes = Counter(depot.bugs.num_dups());
+        self.num_flaky = Counter(depot.num_flaky.load(Ordering::Relaxed));
+        self.targets = depot.targets.summary();
+        self.num_targets = Counter(self.targets.reached);
     }
 
     fn iter_pq(&mut self, depot: &Arc<Depot>) {
@@ -150,7 +166,8 @@ impl fmt::Display for ChartStats {
   COVERAGE |    EDGE: {},   DENSITY: {}%
     EXECS  |   TOTAL: {},     ROUND: {},     MAX_R: {}
     SPEED  |  PERIOD: {:6}r/s    TIME: {}us, 
-    FOUND  |    PATH: {},     HANGS: {},   CRASHES: {} 
+    FOUND  |    PATH: {},     HANGS: {},   CRASHES: {},   BUGS: {} ({} dups),   FLAKY: {}
+   TARGETS | {}
 {}
 {}
 {}
@@ -173,6 +190,10 @@ impl fmt::Display for ChartStats {
             self.num_inputs,
             self.num_hangs,
             self.num_crashes,
+            self.num_bugs,
+            self.num_dup_crashes,
+            self.num_flaky,
+            self.targets,
             " -- FUZZ -- ".blue().bold(),
             self.fuzz,
             " -- SEARCH -- ".blue().bold(),
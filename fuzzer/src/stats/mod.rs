@@ -10,8 +10,9 @@ mod local;
 mod search;
 mod show;
 mod state;
+mod target;
 
-pub use self::{bunny::*, chart::*, entry::*, local::*};
+pub use self::{bunny::*, chart::*, entry::*, local::*, target::*};
 use self::{fuzz::*, search::*, state::*};
 
 pub use self::{format::*, show::*};
@@ -36,4 +36,7 @@ pub fn show_stats(
         )
         .expect("Unable to write!");
     }
+    if let Err(e) = depot.targets.save(dir) {
+        warn!("Could not write the target progress: {:?}", e);
+    }
 }
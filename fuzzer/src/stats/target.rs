@@ -0,0 +1,252 @@
+use super::*;
+use crate::dyncfg::cfg::CmpId;
+use angora_common::defs;
+use serde_derive::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fmt::Write as FmtWrite,
+    fs, io,
+    path::Path,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+};
+
+// The progress on one target of the cfg file.
+#[derive(Default, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TargetProgress {
+    pub cmpid: CmpId,
+    // Seconds since the start of the campaign.
+    pub reached_time: Option<u64>,
+    // Both branches covered.
+    pub solved_time: Option<u64>,
+    // Executions while fuzzing its conditions.
+    pub num_exec: usize,
+    // The input that reached it first, None if it was a crash.
+    pub first_input: Option<usize>,
+    // A crash was found while fuzzing its conditions.
+    pub crash_found: bool,
+}
+
+#[derive(Default, Clone, Copy, Serialize)]
+pub struct TargetSummary {
+    pub total: usize,
+    pub reached: usize,
+    pub solved: usize,
+    pub crashed: usize,
+}
+
+// What is saved in the state dir to resume the campaign.
+#[derive(Serialize, Deserialize)]
+struct TargetState {
+    elapsed: u64,
+    targets: Vec<TargetProgress>,
+}
+
+pub struct TargetStats {
+    start_time: time::Instant,
+    // The campaign time before it was resumed, in seconds.
+    time_offset: AtomicU64,
+    targets: Mutex<BTreeMap<CmpId, TargetProgress>>,
+}
+
+impl TargetStats {
+    pub fn new<I: IntoIterator<Item = CmpId>>(targets: I) -> Self {
+        let targets = targets
+            .into_iter()
+            .map(|cmpid| {
+                (cmpid, TargetProgress {
+                    cmpid,
+                    ..Default::default()
+                })
+            })
+            .collect();
+        Self {
+            start_time: time::Instant::now(),
+            time_offset: AtomicU64::new(0),
+            targets: Mutex::new(targets),
+        }
+    }
+
+    fn elapsed(&self) -> u64 {
+        self.time_offset.load(Ordering::Relaxed) + self.start_time.elapsed().as_secs()
+    }
+
+    fn targets(&self) -> std::sync::MutexGuard<'_, BTreeMap<CmpId, TargetProgress>> {
+        match self.targets.lock() {
+            Ok(g) => g,
+            Err(p) => p.into_inner(),
+        }
+    }
+
+    // The targets on the path of a new input.
+    pub fn reach(&self, cmps: &[CmpId], input: Option<usize>) {
+        let now = self.elapsed();
+        let mut targets = self.targets();
+        for cmp in cmps {
+            if let Some(t) = targets.get_mut(cmp) {
+                if t.reached_time.is_none() {
+                    t.reached_time = Some(now);
+                    t.first_input = input;
+                }
+            }
+        }
+    }
+
+    pub fn solve(&self, cmp: CmpId) {
+        let now = self.elapsed();
+        if let Some(t) = self.targets().get_mut(&cmp) {
+            if t.solved_time.is_none() {
+                // Solving it reaches it too, e.g. on an input that was not new.
+                t.reached_time.get_or_insert(now);
+                t.solved_time = Some(now);
+            }
+        }
+    }
+
+    // After a round of fuzzing one of its conditions.
+    pub fn fuzzed(&self, cmp: CmpId, num_exec: usize, crash_found: bool) {
+        if let Some(t) = self.targets().get_mut(&cmp) {
+            t.num_exec += num_exec;
+            t.crash_found |= crash_found;
+        }
+    }
+
+    pub fn summary(&self) -> TargetSummary {
+        let targets = self.targets();
+        TargetSummary {
+            total: targets.len(),
+            reached: targets.values().filter(|t| t.reached_time.is_some()).count(),
+            solved: targets.values().filter(|t| t.solved_time.is_some()).count(),
+            crashed: targets.values().filter(|t| t.crash_found).count(),
+        }
+    }
+
+    pub fn progress(&self) -> Vec<TargetProgress> {
+        self.targets().values().cloned().collect()
+    }
+
+    // One row per target, empty fields for what did not happen yet.
+    pub fn to_csv(&self) -> String {
+        let opt = |v: Option<u64>| v.map(|v| v.to_string()).unwrap_or_default();
+        let mut out = String::from("cmpid,reached_time,solved_time,num_exec,first_input,crash_found\n");
+        for t in self.progress() {
+            writeln!(
+                out,
+                "{},{},{},{},{},{}",
+                t.cmpid,
+                opt(t.reached_time),
+                opt(t.solved_time),
+                t.num_exec,
+                opt(t.first_input.map(|id| id as u64)),
+                t.crash_found
+            )
+            .unwrap();
+        }
+        out
+    }
+
+    pub fn save(&self, dir: &Path) -> io::Result<()> {
+        fs::write(
+            dir.join(defs::TARGET_PROGRESS_JSON_FILE),
+            serde_json::to_string(&self.progress())?,
+        )?;
+        fs::write(dir.join(defs::TARGET_PROGRESS_CSV_FILE), self.to_csv())
+    }
+
+    pub fn save_state(&self, dir: &Path) -> io::Result<()> {
+        let state = TargetState {
+            elapsed: self.elapsed(),
+            targets: self.progress(),
+        };
+        fs::write(dir.join(defs::TARGET_STATE_FILE), serde_json::to_string(&state)?)
+    }
+
+    // The times go on from the saved campaign. A target only solved in the cfg
+    // (e.g. by an imported one) stays solved.
+    pub fn load_state(&self, dir: &Path) -> io::Result<()> {
+        let state: TargetState = serde_json::from_slice(&fs::read(dir.join(defs::TARGET_STATE_FILE))?)?;
+        self.time_offset.store(state.elapsed, Ordering::Relaxed);
+        let mut targets = self.targets();
+        for saved in state.targets {
+            if let Some(t) = targets.get_mut(&saved.cmpid) {
+                let solved_time = saved.solved_time.or(t.solved_time);
+                let reached_time = saved.reached_time.or(t.reached_time);
+                *t = TargetProgress {
+                    reached_time,
+                    solved_time,
+                    ..saved
+                };
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for TargetSummary {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "REACHED: {} / {},   SOLVED: {},   CRASHED: {}",
+            Counter(self.reached),
+            Counter(self.total),
+            Counter(self.solved),
+            Counter(self.crashed)
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn target_progress() {
+        let stats = TargetStats::new(vec![10, 20, 30]);
+        stats.reach(&[20, 40], Some(3));
+        stats.reach(&[20], Some(5));
+        stats.fuzzed(20, 100, false);
+        stats.fuzzed(20, 50, true);
+        stats.fuzzed(40, 10, true);
+        stats.solve(30);
+
+        let progress = stats.progress();
+        assert_eq!(progress.len(), 3);
+        assert_eq!(progress[0].reached_time, None);
+        assert_eq!(progress[1].first_input, Some(3));
+        assert_eq!(progress[1].num_exec, 150);
+        assert!(progress[1].crash_found);
+        assert_eq!(progress[2].reached_time, Some(0));
+        assert_eq!(progress[2].solved_time, Some(0));
+
+        let summary = stats.summary();
+        assert_eq!((summary.total, summary.reached, summary.solved, summary.crashed), (3, 2, 1, 1));
+        assert_eq!(stats.to_csv().lines().nth(1), Some("10,,,0,,false"));
+    }
+
+    #[test]
+    fn state_save_load() {
+        let dir = std::env::temp_dir().join(format!("parmesan_targets_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let stats = TargetStats::new(vec![10, 20, 30]);
+        stats.time_offset.store(100, Ordering::Relaxed);
+        stats.reach(&[20], Some(3));
+        stats.fuzzed(20, 150, true);
+        stats.save_state(&dir).unwrap();
+
+        // 30 was solved in the saved cfg.
+        let loaded = TargetStats::new(vec![10, 20, 30]);
+        loaded.solve(30);
+        loaded.load_state(&dir).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+        let progress = loaded.progress();
+        assert_eq!(progress[0], stats.progress()[0]);
+        assert_eq!(progress[1], stats.progress()[1]);
+        assert_eq!(progress[1].reached_time, Some(100));
+        assert!(progress[2].solved_time.is_some());
+        assert!(loaded.elapsed() >= 100);
+        loaded.solve(10);
+        assert!(loaded.progress()[0].solved_time.unwrap() >= 100);
+    }
+}
@@ -0,0 +1,217 @@
+// Test case minimization: shrink an input while it still crashes the same way,
+// hits the same edges, or reaches the same condition.
+use crate::{
+    command,
+    cond_stmt::CondStmt,
+    depot::read_from_file,
+    executor::{Runner, StatusType},
+};
+use angora_common::defs;
+use std::{fs, path::Path};
+
+// Like AFL's tmin: the first blocks are 1/16 of the input.
+const TMIN_START_STEPS: usize = 16;
+const TMIN_NORM_BYTE: u8 = b'0';
+
+// What the minimized input must keep.
+enum Property {
+    // The same bug, by the signature of the crash report if it has a stack.
+    Crash(Option<u64>),
+    // With the same status, a crashing input keeps crashing with -e.
+    Edges(StatusType, Vec<(usize, u8)>),
+    // Reach the condition.
+    Target(CondStmt),
+}
+
+// CMPID or CMPID:CONTEXT
+fn parse_target(s: &str) -> (u32, Option<u32>) {
+    let mut parts = s.splitn(2, ':');
+    let cmpid = parts
+        .next()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or_else(|| panic!("Invalid target {:?}", s));
+    let context = parts
+        .next()
+        .map(|v| v.parse().unwrap_or_else(|_| panic!("Invalid target {:?}", s)));
+    (cmpid, context)
+}
+
+// The fast program only reports the condition in the exact context,
+// so without one, take the context and order of the first match when tracking the input.
+fn find_target(
+    runner: &mut Runner,
+    track_target: Option<&str>,
+    buf: &Vec<u8>,
+    cmpid: u32,
+    context: Option<u32>,
+) -> CondStmt {
+    let mut cond = CondStmt::new();
+    cond.base.cmpid = cmpid;
+    match (context, track_target) {
+        (Some(context), _) => {
+            cond.base.context = context;
+            cond.base.order = 1;
+        },
+        (None, Some(track_target)) => {
+            let cond_list = runner
+                .track(track_target, buf)
+                .unwrap_or_else(|e| panic!("Could not track the input: {}", e));
+            let found = cond_list
+                .iter()
+                .find(|c| c.base.cmpid == cmpid)
+                .unwrap_or_else(|| panic!("cmpid {} is not reached by the input when tracking", cmpid));
+            info!(
+                "Target cmpid {}: context {}, order {}",
+                cmpid, found.base.context, found.base.order
+            );
+            cond.base.context = found.base.context;
+            cond.base.order = found.base.order;
+        },
+        (None, None) => panic!(
+            "The context of cmpid {} is unknown, give it as CMPID:CONTEXT, or give the track program with --track",
+            cmpid
+        ),
+    }
+    cond
+}
+
+fn keeps(runner: &mut Runner, prop: &Property, buf: &Vec<u8>) -> bool {
+    match prop {
+        Property::Crash(sig) => {
+            runner.run(buf) == StatusType::Crash
+                && (sig.is_none() || runner.reproduce(buf).signature() == *sig)
+        },
+        Property::Edges(status, path) => runner.run(buf) == *status && runner.get_path() == *path,
+        Property::Target(cond) => {
+            let (status, output) = runner.run_with_cond(buf, cond);
+            status != StatusType::Timeout && output != defs::UNREACHABLE
+        },
+    }
+}
+
+// Remove blocks, halving their size down to single bytes, then replace bytes with '0'.
+// Repeat until nothing changes.
+pub fn minimize<F: FnMut(&Vec<u8>) -> bool>(buf: &[u8], mut keeps: F) -> Vec<u8> {
+    let mut buf = buf.to_vec();
+    loop {
+        let len_before = buf.len();
+        let mut del_len = (buf.len() / TMIN_START_STEPS).next_power_of_two().max(1);
+        loop {
+            let mut pos = 0;
+            while pos < buf.len() {
+                let end = (pos + del_len).min(buf.len());
+                let mut new_buf = buf[..pos].to_vec();
+                new_buf.extend_from_slice(&buf[end..]);
+                if keeps(&new_buf) {
+                    buf = new_buf;
+                } else {
+                    pos += del_len;
+                }
+            }
+            if del_len == 1 {
+                break;
+            }
+            del_len /= 2;
+        }
+
+        let mut changed = false;
+        let all_norm = vec![TMIN_NORM_BYTE; buf.len()];
+        if buf != all_norm && keeps(&all_norm) {
+            buf = all_norm;
+            changed = true;
+        }
+        for i in 0..buf.len() {
+            if buf[i] == TMIN_NORM_BYTE {
+                continue;
+            }
+            let mut new_buf = buf.clone();
+            new_buf[i] = TMIN_NORM_BYTE;
+            if keeps(&new_buf) {
+                buf = new_buf;
+                changed = true;
+            }
+        }
+
+        if !changed && buf.len() == len_before {
+            return buf;
+        }
+    }
+}
+
+pub fn tmin_main(
+    in_file: &str,
+    out_file: Option<&str>,
+    pargs: Vec<String>,
+    mem_limit: u64,
+    time_limit: Option<&str>,
+    target: Option<&str>,
+    track_target: Option<&str>,
+    edges_only: bool,
+) {
+    pretty_env_logger::init();
+
+    let time_limit = command::parse_tool_time_limit(time_limit);
+    let buf = read_from_file(Path::new(in_file));
+    let mut runner = Runner::new(pargs, time_limit, mem_limit);
+
+    let prop = match target {
+        Some(target) => {
+            let (cmpid, context) = parse_target(target);
+            Property::Target(find_target(&mut runner, track_target, &buf, cmpid, context))
+        },
+        None => match runner.run(&buf) {
+            StatusType::Crash if !edges_only => {
+                let report = runner.reproduce(&buf);
+                if report.signature().is_none() {
+                    warn!("The crash has no stack trace, only keep it crashing");
+                }
+                Property::Crash(report.signature())
+            },
+            status @ StatusType::Normal | status @ StatusType::Crash => {
+                Property::Edges(status, runner.get_path())
+            },
+            status => panic!("The input does not run normally: {:?}", status),
+        },
+    };
+    if !keeps(&mut runner, &prop, &buf) {
+        panic!("The input does not have the property to keep, is the program deterministic?");
+    }
+
+    let mut num_runs = 0;
+    let min_buf = minimize(&buf, |b| {
+        num_runs += 1;
+        keeps(&mut runner, &prop, b)
+    });
+
+    let out_file = match out_file {
+        Some(f) => f.to_string(),
+        None => format!("{}.min", in_file),
+    };
+    fs::write(&out_file, &min_buf).expect("Could not write the output file");
+    println!(
+        "Minimized {} bytes to {} bytes in {} runs, saved to {}",
+        buf.len(),
+        min_buf.len(),
+        num_runs,
+        out_file
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_target_context() {
+        assert_eq!(parse_target("12"), (12, None));
+        assert_eq!(parse_target("12:34"), (12, Some(34)));
+    }
+
+    #[test]
+    fn minimize_input() {
+        let keeps = |b: &Vec<u8>| twoway::find_bytes(b, b"AB").is_some();
+        assert_eq!(minimize(b"xxxxABxxxxxxxxxxxxxxxxx", keeps), b"AB".to_vec());
+        let keeps = |b: &Vec<u8>| b.len() >= 3;
+        assert_eq!(minimize(b"hello world", keeps), b"000".to_vec());
+    }
+}
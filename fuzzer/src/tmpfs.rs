@@ -16,7 +16,8 @@ pub fn create_tmpfs_dir(target: &Path) {
         let dir_name = format!("angora_tmp_{}", pid);
         let tmp_dir = shm_dir.join(dir_name);
         fs::create_dir(&tmp_dir).unwrap();
-        if target.exists() {
+        // The link may be left dangling by a previous run, e.g. after a reboot.
+        if fs::symlink_metadata(target).is_ok() {
             fs::remove_file(target).unwrap();
         }
         symlink(&tmp_dir, target).unwrap();
@@ -26,12 +27,14 @@ pub fn create_tmpfs_dir(target: &Path) {
             "System does not have {} directory! Can't use tmpfs.",
             LINUX_TMPFS_DIR
         );
-        fs::create_dir(&target).unwrap();
+        if !target.is_dir() {
+            fs::create_dir(&target).unwrap();
+        }
     }
 }
 
 pub fn clear_tmpfs_dir(target: &Path) {
-    if target.exists() {
+    if fs::symlink_metadata(target).is_ok() {
         fs::remove_file(target).unwrap();
     }
     let shm_dir = Path::new(LINUX_TMPFS_DIR);
@@ -0,0 +1,139 @@
+// Unique bugs, by the signature of their crash reports.
+use super::CrashReport;
+use angora_common::defs;
+use std::{
+    collections::HashMap,
+    path::Path,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+};
+
+#[derive(Default)]
+struct Bug {
+    num_crashes: usize,
+    // If one of the crashes is deterministic, i.e. saved in crashes/ rather than crashes/flaky.
+    deterministic: bool,
+}
+
+#[derive(Default)]
+pub struct BugSet {
+    // signature -> bug
+    bugs: Mutex<HashMap<u64, Bug>>,
+    num_dups: AtomicUsize,
+}
+
+impl BugSet {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    // Rebuild from the reports in the crash directories of a resumed campaign.
+    pub fn load(crashes_dir: &Path, flaky_dir: &Path) -> Self {
+        let bugs = Self::new();
+        for &(dir, deterministic) in &[(crashes_dir, true), (flaky_dir, false)] {
+            let entries = match dir.read_dir() {
+                Ok(entries) => entries,
+                Err(_) => continue,
+            };
+            for entry in entries.flatten() {
+                let path = entry.path();
+                if path.extension().and_then(|ext| ext.to_str()) == Some(defs::CRASH_REPORT_EXT) {
+                    match CrashReport::load(&path) {
+                        Ok(report) => {
+                            bugs.add(&report, deterministic);
+                        },
+                        Err(e) => warn!("Could not load crash report {:?}: {:?}", path, e),
+                    }
+                }
+            }
+        }
+        bugs
+    }
+
+    // Returns false if we have seen the bug before.
+    // A deterministic crash of a bug that only crashed flakily so far is kept,
+    // so that each bug has a reproducible crash if there is one.
+    pub fn add(&self, report: &CrashReport, deterministic: bool) -> bool {
+        // Without a stack trace, all the crashes with the same status are one bug.
+        let sig = report.signature().unwrap_or_else(|| report.status_signature());
+        let mut bugs = match self.bugs.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => {
+                warn!("Mutex poisoned! Results may be incorrect. Continuing...");
+                poisoned.into_inner()
+            },
+        };
+        let bug = bugs.entry(sig).or_default();
+        let is_new = bug.num_crashes == 0 || (deterministic && !bug.deterministic);
+        bug.num_crashes += 1;
+        bug.deterministic |= deterministic;
+        if !is_new {
+            self.num_dups.fetch_add(1, Ordering::Relaxed);
+        }
+        is_new
+    }
+
+    pub fn num_unique(&self) -> usize {
+        match self.bugs.lock() {
+            Ok(guard) => guard.len(),
+            Err(poisoned) => poisoned.into_inner().len(),
+        }
+    }
+
+    pub fn num_dups(&self) -> usize {
+        self.num_dups.load(Ordering::Relaxed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dedup() {
+        let bugs = BugSet::new();
+        let a = CrashReport::parse(
+            "==1==ERROR: AddressSanitizer: SEGV on unknown address\n    #0 0x4f5b2a in parse_header /src/foo.c:12:5\n",
+            "signal 6",
+        );
+        let b = CrashReport::parse("", "signal 11");
+        assert!(bugs.add(&a, true));
+        assert!(!bugs.add(&a, true));
+        assert!(bugs.add(&b, true));
+        assert!(!bugs.add(&b, true));
+        assert_eq!(bugs.num_unique(), 2);
+        assert_eq!(bugs.num_dups(), 2);
+    }
+
+    #[test]
+    fn dedup_without_stack() {
+        let bugs = BugSet::new();
+        let a = CrashReport::parse("Segmentation fault\n", "signal 11");
+        let b = CrashReport::parse("", "signal 11");
+        let c = CrashReport::parse("", "signal 6");
+        assert!(bugs.add(&a, true));
+        assert!(!bugs.add(&b, true));
+        assert!(bugs.add(&c, true));
+        assert_eq!(bugs.num_unique(), 2);
+        assert_eq!(bugs.num_dups(), 1);
+    }
+
+    #[test]
+    fn promote_flaky() {
+        let bugs = BugSet::new();
+        let a = CrashReport::parse(
+            "==1==ERROR: AddressSanitizer: heap-use-after-free on address\n    #0 0x4f5b2a in parse_header /src/foo.c:12:5\n",
+            "signal 6",
+        );
+        assert!(bugs.add(&a, false));
+        assert!(!bugs.add(&a, false));
+        // The first deterministic crash of the bug is saved, the next ones are not.
+        assert!(bugs.add(&a, true));
+        assert!(!bugs.add(&a, true));
+        assert!(!bugs.add(&a, false));
+        assert_eq!(bugs.num_unique(), 1);
+        assert_eq!(bugs.num_dups(), 3);
+    }
+}
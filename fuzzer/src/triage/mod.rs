@@ -0,0 +1,5 @@
+mod dedup;
+mod report;
+mod verify;
+
+pub use self::{dedup::BugSet, report::{describe_status, report_envs, CrashReport}, verify::Verification};
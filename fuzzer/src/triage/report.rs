@@ -0,0 +1,238 @@
+// The sanitizer report of a crash, saved as crashes/id:NNNNNN.report
+use super::Verification;
+use angora_common::{config, defs};
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap},
+    env, fmt, fs,
+    hash::{Hash, Hasher},
+    io,
+    os::unix::process::ExitStatusExt,
+    path::Path,
+    process::ExitStatus,
+};
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CrashReport {
+    // e.g. heap-buffer-overflow, SEGV, use-of-uninitialized-value
+    pub bug_type: Option<String>,
+    // The top frames of the first stack trace, outside the sanitizer runtime.
+    pub frames: Vec<String>,
+    // How the reproduction run ended.
+    pub status: String,
+    pub verification: Option<Verification>,
+    pub stderr: String,
+}
+
+// Frames of the sanitizer runtime itself, e.g. __interceptor_memcpy.
+fn is_runtime_frame(frame: &str) -> bool {
+    frame.contains(" in __interceptor_")
+        || frame.contains(" in __asan")
+        || frame.contains(" in __msan")
+        || frame.contains(" in __ubsan")
+        || frame.contains(" in __sanitizer")
+        || frame.contains("compiler-rt/lib/")
+}
+
+fn parse_bug_type(line: &str) -> Option<String> {
+    // ==1==ERROR: AddressSanitizer: heap-buffer-overflow on address ...
+    // ==1==WARNING: MemorySanitizer: use-of-uninitialized-value
+    for tag in &["ERROR: ", "WARNING: "] {
+        if let Some(pos) = line.find(tag) {
+            let rest = &line[pos + tag.len()..];
+            if let Some(pos) = rest.find("Sanitizer: ") {
+                return rest[pos + "Sanitizer: ".len()..]
+                    .split_whitespace()
+                    .next()
+                    .map(|s| s.to_string());
+            }
+        }
+    }
+    // foo.c:12:5: runtime error: signed integer overflow: ...
+    if let Some(pos) = line.find("runtime error: ") {
+        let rest = &line[pos + "runtime error: ".len()..];
+        return Some(rest.split(':').next().unwrap_or(rest).trim().to_string());
+    }
+    None
+}
+
+// The function of a frame, or the module offset if it is not symbolized:
+//   #1 0x4f5b2a in parse_header /src/foo.c:12:5
+//   #1 0x4f5b2a  (/path/to/prog+0xf5b2a)
+fn frame_key(frame: &str) -> &str {
+    if let Some(pos) = frame.find(" in ") {
+        return frame[pos + 4..].split_whitespace().next().unwrap_or("");
+    }
+    match (frame.find('('), frame.rfind(')')) {
+        (Some(start), Some(end)) if start < end => &frame[start + 1..end],
+        _ => frame,
+    }
+}
+
+// The envs to run a crashing input again with symbolized sanitizer output.
+pub fn report_envs(envs: &HashMap<String, String>) -> HashMap<String, String> {
+    let mut envs = envs.clone();
+    envs.insert(
+        defs::ASAN_OPTIONS_VAR.to_string(),
+        defs::ASAN_OPTIONS_REPORT_CONTENT.to_string(),
+    );
+    envs.insert(
+        defs::MSAN_OPTIONS_VAR.to_string(),
+        defs::MSAN_OPTIONS_REPORT_CONTENT.to_string(),
+    );
+    // To find llvm-symbolizer.
+    if let Ok(path) = env::var("PATH") {
+        envs.insert("PATH".to_string(), path);
+    }
+    envs
+}
+
+// How the reproduction run ended, None if it timed out.
+pub fn describe_status(status: Option<ExitStatus>) -> String {
+    match status {
+        Some(status) => match (status.code(), status.signal()) {
+            (Some(code), _) => format!("exit {}", code),
+            (_, Some(sig)) => format!("signal {}", sig),
+            _ => format!("{:?}", status),
+        },
+        None => String::from("timeout"),
+    }
+}
+
+impl CrashReport {
+    pub fn parse(stderr: &str, status: &str) -> Self {
+        let mut bug_type = None;
+        let mut frames = vec![];
+        let mut stack_done = false;
+        for line in stderr.lines() {
+            let line = line.trim();
+            if bug_type.is_none() {
+                bug_type = parse_bug_type(line);
+            }
+            // #0 0x4f5b2a in parse_header /src/foo.c:12:5
+            if line.starts_with('#') {
+                if !stack_done
+                    && frames.len() < config::CRASH_STACK_DEPTH
+                    && !is_runtime_frame(line)
+                {
+                    frames.push(line.to_string());
+                }
+            } else if !frames.is_empty() {
+                // Only the first stack, not the ones of the allocation or free.
+                stack_done = true;
+            }
+        }
+        Self {
+            bug_type,
+            frames,
+            status: status.to_string(),
+            verification: None,
+            stderr: stderr.to_string(),
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        fs::write(path, self.to_string())
+    }
+
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let content = fs::read_to_string(path)?;
+        let mut parts = content.splitn(2, "\n\n");
+        let header = parts.next().unwrap_or("");
+        let stderr = parts.next().unwrap_or("");
+        let status = header
+            .lines()
+            .find_map(|l| l.strip_prefix("status: "))
+            .unwrap_or("");
+        Ok(Self::parse(stderr, status))
+    }
+
+    // Same bug type and top frames: the same bug.
+    // None if there is no stack to compare.
+    pub fn signature(&self) -> Option<u64> {
+        if self.frames.is_empty() {
+            return None;
+        }
+        let mut hasher = DefaultHasher::new();
+        self.bug_type.hash(&mut hasher);
+        for frame in &self.frames {
+            frame_key(frame).hash(&mut hasher);
+        }
+        Some(hasher.finish())
+    }
+
+    // For crashes without a stack, e.g. on a program without sanitizers:
+    // the same bug type (if any) and the same signal or exit code.
+    pub fn status_signature(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        "no stack".hash(&mut hasher);
+        self.bug_type.hash(&mut hasher);
+        self.status.hash(&mut hasher);
+        hasher.finish()
+    }
+}
+
+impl fmt::Display for CrashReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "bug type: {}", self.bug_type.as_deref().unwrap_or("unknown"))?;
+        match self.frames.first() {
+            Some(frame) => writeln!(f, "frame: {}", frame)?,
+            None => writeln!(f, "frame: unknown")?,
+        }
+        writeln!(f, "status: {}", self.status)?;
+        if let Some(verification) = self.verification {
+            writeln!(f, "reproduced: {}", verification)?;
+        }
+        writeln!(f)?;
+        write!(f, "{}", self.stderr)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_asan() {
+        let stderr = "=================================================================\n\
+==42==ERROR: AddressSanitizer: heap-buffer-overflow on address 0x602000000011 at pc 0x4c3e10\n\
+READ of size 2 at 0x602000000011 thread T0\n\
+    #0 0x4c3e0f in __interceptor_memcpy /llvm/compiler-rt/lib/asan/asan_interceptors.cc:22\n\
+    #1 0x4f5b2a in parse_header /src/foo.c:12:5\n\
+    #2 0x4f5c00 in main /src/foo.c:30:3\n";
+        let report = CrashReport::parse(stderr, "signal 6");
+        assert_eq!(report.bug_type.as_deref(), Some("heap-buffer-overflow"));
+        assert_eq!(
+            report.frames.first().map(|s| s.as_str()),
+            Some("#1 0x4f5b2a in parse_header /src/foo.c:12:5")
+        );
+        assert_eq!(report.frames.len(), 2);
+    }
+
+    #[test]
+    fn signature() {
+        let a = CrashReport::parse(
+            "==1==ERROR: AddressSanitizer: SEGV on unknown address\n    #0 0x4f5b2a in parse_header /src/foo.c:12:5\n",
+            "signal 6",
+        );
+        let b = CrashReport::parse(
+            "==2==ERROR: AddressSanitizer: SEGV on unknown address\n    #0 0x5f5b2a in parse_header /src/foo.c:12:5\n",
+            "signal 6",
+        );
+        let c = CrashReport::parse(
+            "==1==ERROR: AddressSanitizer: SEGV on unknown address\n    #0 0x4f5b2a in parse_body /src/foo.c:40:5\n",
+            "signal 6",
+        );
+        assert!(a.signature().is_some());
+        assert_eq!(a.signature(), b.signature());
+        assert_ne!(a.signature(), c.signature());
+        assert_eq!(CrashReport::parse("", "signal 11").signature(), None);
+    }
+
+    #[test]
+    fn parse_ubsan() {
+        let stderr = "/src/foo.c:7:9: runtime error: signed integer overflow: 2147483647 + 1 cannot be represented in type 'int'\n";
+        let report = CrashReport::parse(stderr, "exit 1");
+        assert_eq!(report.bug_type.as_deref(), Some("signed integer overflow"));
+        assert!(report.frames.is_empty());
+    }
+}
@@ -0,0 +1,46 @@
+// How often a new crash reproduces, on the fast and the sanopt programs.
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Verification {
+    pub runs: usize,
+    pub fast: usize,
+    // None if there is no separate sanopt program.
+    pub sanopt: Option<usize>,
+}
+
+impl Verification {
+    // Crashes on every run of at least one of the programs.
+    pub fn is_deterministic(&self) -> bool {
+        self.runs > 0 && (self.fast == self.runs || self.sanopt == Some(self.runs))
+    }
+}
+
+impl fmt::Display for Verification {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "fast {}/{}", self.fast, self.runs)?;
+        if let Some(sanopt) = self.sanopt {
+            write!(f, ", sanopt {}/{}", sanopt, self.runs)?;
+        }
+        if !self.is_deterministic() {
+            write!(f, " (flaky)")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn deterministic() {
+        let v = Verification { runs: 3, fast: 0, sanopt: Some(3) };
+        assert!(v.is_deterministic());
+        let v = Verification { runs: 3, fast: 2, sanopt: Some(1) };
+        assert!(!v.is_deterministic());
+        assert_eq!(v.to_string(), "fast 2/3, sanopt 1/3 (flaky)");
+        let v = Verification { runs: 3, fast: 3, sanopt: None };
+        assert!(v.is_deterministic());
+    }
+}
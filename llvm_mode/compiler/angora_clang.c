@@ -252,6 +252,11 @@ static void edit_params(u32 argc, char **argv) {
     if (!strcmp(cur, "-Wl,-z,defs") || !strcmp(cur, "-Wl,--no-undefined"))
       continue;
 
+    // libFuzzer harnesses get main() from our runtime instead of libFuzzer.
+    if (!strcmp(cur, "-fsanitize=fuzzer") ||
+        !strcmp(cur, "-fsanitize=fuzzer-no-link"))
+      continue;
+
     cc_params[cc_par_cnt++] = cur;
   }
 
@@ -338,7 +343,6 @@ static void edit_params(u32 argc, char **argv) {
 
    */
 
-  /*
   cc_params[cc_par_cnt++] = "-D__ANGORA_LOOP(_A)="
     "({ static volatile char *_B __attribute__((used)); "
     " _B = (char*)\"" PERSIST_SIG "\"; "
@@ -351,6 +355,7 @@ static void edit_params(u32 argc, char **argv) {
 #endif
     "_L(_A); })";
 
+  /*
   cc_params[cc_par_cnt++] = "-D__ANGORA_INIT()="
     "do { static volatile char *_A __attribute__((used)); "
     " _A = (char*)\"" DEFER_SIG "\"; "
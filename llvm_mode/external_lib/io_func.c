@@ -25,6 +25,11 @@
 static int granularity = 1; // byte level
 
 extern void __angora_track_fini_rs();
+extern void __angora_start_track_forkcli();
+
+__attribute__((constructor)) void __angora_track_init(void) {
+  __angora_start_track_forkcli();
+}
 
 __attribute__((destructor(0))) void __angora_track_fini(void) {
   __angora_track_fini_rs();
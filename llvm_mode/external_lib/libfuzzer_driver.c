@@ -0,0 +1,67 @@
+/*
+  main() for libFuzzer style harnesses in the track program,
+  the counterpart of runtime_fast/src/libfuzzer_driver.c.
+
+  It is a separate member of libDFSanIO.a, so the linker only pulls it in
+  if the program does not define main itself.
+  This file is not instrumented, so we read the input (@@ or stdin)
+  and assign the taint labels of the offsets ourselves.
+  The entry points are uninstrumented in angora_abilist.txt, otherwise the
+  DFSan pass would rename them to dfs$LLVMFuzzerTestOneInput etc. in the harness.
+  tests/check_libfuzzer.sh checks that a harness links and runs.
+ */
+
+#include <fcntl.h>
+#include <stdint.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <unistd.h>
+
+#include "./dfsan_interface.h"
+
+// config::INPUT_SHM_SIZE
+#define MAX_INPUT_SIZE (1 << 20)
+
+extern int LLVMFuzzerTestOneInput(const uint8_t *data, size_t size);
+__attribute__((weak)) int LLVMFuzzerInitialize(int *argc, char ***argv);
+
+// A harness reading only from memory uses nothing else of io_func.c, and the
+// archive is not linked whole, so pull it in for its constructor and destructor:
+// they start the track fork server and write the track output.
+extern void __angora_track_init(void);
+static void (*keep_io_func)(void) __attribute__((used)) = __angora_track_init;
+
+int main(int argc, char **argv) {
+  if (LLVMFuzzerInitialize)
+    LLVMFuzzerInitialize(&argc, &argv);
+
+  int fd = 0;
+  if (argc > 1) {
+    fd = open(argv[1], O_RDONLY);
+    if (fd < 0) {
+      perror("Could not open input file");
+      exit(1);
+    }
+  }
+
+  uint8_t *buf = malloc(MAX_INPUT_SIZE);
+  size_t len = 0;
+  ssize_t n;
+  while (len < MAX_INPUT_SIZE &&
+         (n = read(fd, buf + len, MAX_INPUT_SIZE - len)) > 0) {
+    len += n;
+  }
+  if (fd != 0)
+    close(fd);
+
+  // byte level, as in io_func.c
+  for (size_t i = 0; i < len; i++) {
+    dfsan_label L = dfsan_create_label(i);
+    dfsan_set_label(L, buf + i, 1);
+  }
+
+  LLVMFuzzerTestOneInput(buf, len);
+
+  free(buf);
+  return 0;
+}
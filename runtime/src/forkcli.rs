@@ -0,0 +1,68 @@
+// Fork server for the track program, like runtime_fast/src/forkcli.rs.
+// server: fuzzer/src/executor/forksrv.rs (run_track)
+// For each run, the fuzzer sends the signal, then the length and the path of the track output file.
+use angora_common::{config, defs};
+use std::{env, io::prelude::*, os::unix::net::UnixStream, time::Duration};
+
+// The parent must not run the destructors (__angora_track_fini), which would write a track file.
+fn exit_forkcli(code: i32) -> ! {
+    unsafe { libc::_exit(code) }
+}
+
+fn read_track_path(socket: &mut UnixStream) -> Option<String> {
+    let mut sig_buf = [0; 4];
+    socket.read_exact(&mut sig_buf).ok()?;
+    let mut len_buf = [0; 4];
+    socket.read_exact(&mut len_buf).ok()?;
+    let mut path_buf = vec![0; u32::from_le_bytes(len_buf) as usize];
+    socket.read_exact(&mut path_buf).ok()?;
+    String::from_utf8(path_buf).ok()
+}
+
+pub fn start_forkcli() {
+    let socket_path = match env::var(defs::TRACK_FORKSRV_SOCKET_PATH_VAR) {
+        Ok(p) => p,
+        Err(_) => return,
+    };
+    let mut socket = match UnixStream::connect(socket_path) {
+        Ok(sock) => sock,
+        Err(e) => {
+            eprintln!("Couldn't connect: {:?}", e);
+            return;
+        },
+    };
+
+    // No read timeout: only inputs with new paths are tracked, so we may wait for a long time.
+    // We exit once the fuzzer closes the socket.
+    socket
+        .set_write_timeout(Some(Duration::from_millis(config::TIME_LIMIT_TRACK * 2)))
+        .expect("Couldn't set write timeout");
+
+    loop {
+        let track_path = match read_track_path(&mut socket) {
+            Some(p) => p,
+            None => exit_forkcli(0),
+        };
+
+        let child_pid = unsafe { libc::fork() };
+
+        if child_pid == 0 {
+            env::set_var(defs::TRACK_OUTPUT_VAR, &track_path);
+            super::track::reset_logger();
+            return;
+        }
+
+        if socket.write_all(&child_pid.to_le_bytes()).is_err() {
+            exit_forkcli(1);
+        }
+
+        let mut status: libc::c_int = 0;
+        if unsafe { libc::waitpid(child_pid, &mut status as *mut libc::c_int, 0) } < 0 {
+            exit_forkcli(1);
+        }
+
+        if socket.write_all(&status.to_le_bytes()).is_err() {
+            exit_forkcli(1);
+        }
+    }
+}
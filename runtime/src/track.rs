@@ -1,9 +1,15 @@
 use super::*;
-use crate::tag_set_wrap;
+use crate::{forkcli, tag_set_wrap};
 use angora_common::{cond_stmt_base::*, defs};
 use lazy_static::lazy_static;
 use libc;
-use std::{slice, sync::Mutex};
+use std::{
+    slice,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
+};
 
 // use shm_conds;
 lazy_static! {
@@ -330,3 +336,32 @@ pub extern "C" fn __angora_track_fini_rs() {
     let mut lcl = LC.lock().expect("Could not lock LC.");
     *lcl = None;
 }
+
+// Called by the constructor in io_func.c, returns in the child if we run under the fork server.
+#[no_mangle]
+pub extern "C" fn __angora_start_track_forkcli() {
+    forkcli::start_forkcli();
+}
+
+// In the child of the fork server, log to the output file of this run.
+pub(crate) fn reset_logger() {
+    let mut lcl = LC.lock().expect("Could not lock LC.");
+    // Anything logged before the fork belongs to no run, don't write it out.
+    if let Some(old) = lcl.replace(Logger::new()) {
+        std::mem::forget(old);
+    }
+}
+
+// The track program is run once for each input, so the persistent loop only runs once.
+static PERSISTENT_FIRST_PASS: AtomicBool = AtomicBool::new(true);
+
+#[no_mangle]
+pub extern "C" fn __angora_persistent_loop(_max_cnt: u32) -> i32 {
+    PERSISTENT_FIRST_PASS.swap(false, Ordering::Relaxed) as i32
+}
+
+// The track program reads the input from the file (or stdin) so that the bytes are tainted.
+#[no_mangle]
+pub extern "C" fn __angora_input_buf(_len: *mut usize) -> *const u8 {
+    std::ptr::null()
+}
@@ -4,4 +4,7 @@ fn main() {
     cc::Build::new()
         .file("src/context.c")
         .compile("libcontext.a");
-}
\ No newline at end of file
+    cc::Build::new()
+        .file("src/libfuzzer_driver.c")
+        .compile("libfuzzer_driver.a");
+}
@@ -1,4 +1,4 @@
-use super::{shm_conds, forkcli, shm_branches};
+use super::{shm_conds, forkcli, shm_branches, shm_input};
 use std::ops::DerefMut;
 use std::ptr;
 use std::sync::Once;
@@ -15,6 +15,7 @@ extern "C" {
 fn fast_init() { 
     START.call_once(|| {
         shm_branches::map_branch_counting_shm();
+        shm_input::map_input_shm();
         forkcli::start_forkcli();
     });
 }
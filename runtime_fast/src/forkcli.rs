@@ -4,7 +4,27 @@ use std::env;
 
 use byteorder::{LittleEndian, WriteBytesExt};
 use libc;
-use std::{io::prelude::*, os::unix::net::UnixStream, process, time::Duration};
+use std::{
+    io::prelude::*,
+    os::unix::net::UnixStream,
+    process,
+    sync::atomic::{AtomicBool, Ordering},
+    time::Duration,
+};
+
+// Set if the fuzzer asked for persistent mode and we are running under the fork server.
+pub static PERSISTENT_MODE: AtomicBool = AtomicBool::new(false);
+
+fn exit_forkcli(code: i32, child: Option<i32>) -> ! {
+    // Don't leave a (stopped) persistent child behind.
+    if let Some(pid) = child {
+        unsafe {
+            libc::kill(pid, libc::SIGKILL);
+            libc::waitpid(pid, std::ptr::null_mut(), 0);
+        }
+    }
+    process::exit(code);
+}
 
 pub fn start_forkcli() {
     match env::var(defs::FORKSRV_SOCKET_PATH_VAR) {
@@ -17,40 +37,60 @@ pub fn start_forkcli() {
                 },
             };
 
+            // No read timeout: the fuzzer may track inputs for longer than any fixed limit
+            // while we wait. We exit once it closes the socket.
             socket
-                .set_read_timeout(Some(Duration::from_secs(config::TIME_LIMIT_TRACK * 2)))
-                .expect("Couldn't set read timeout");
-            socket
-                .set_write_timeout(Some(Duration::from_secs(config::TIME_LIMIT_TRACK * 2)))
+                .set_write_timeout(Some(Duration::from_millis(config::TIME_LIMIT_TRACK * 2)))
                 .expect("Couldn't set write timeout");
 
+            let persistent = env::var(defs::PERSIST_ENV_VAR).is_ok();
+            PERSISTENT_MODE.store(persistent, Ordering::SeqCst);
+
             let mut sig_buf = [0; 4];
             super::shm_conds::reset_shm_conds();
 
+            // In persistent mode, the child stops itself after each input and waits to be continued.
+            let mut stopped_child: Option<i32> = None;
+
             loop {
-                if socket.read(&mut sig_buf).is_err() {
+                if socket.read_exact(&mut sig_buf).is_err() {
                     eprintln!("exit forkcli");
-                    process::exit(0);
+                    exit_forkcli(0, stopped_child);
                 }
 
-                let child_pid = unsafe { libc::fork() };
-
-                if child_pid == 0 {
-                    super::shm_conds::reset_shm_conds();
-                    return;
-                }
+                let child_pid = match stopped_child.take() {
+                    Some(pid) => {
+                        unsafe {
+                            libc::kill(pid, libc::SIGCONT);
+                        }
+                        pid
+                    },
+                    None => {
+                        let pid = unsafe { libc::fork() };
+                        if pid == 0 {
+                            super::shm_conds::reset_shm_conds();
+                            return;
+                        }
+                        pid
+                    },
+                };
 
                 let mut pid_buf = vec![];
                 pid_buf
                     .write_i32::<LittleEndian>(child_pid)
                     .expect("Could not write to child.");
                 if socket.write(&pid_buf).is_err() {
-                    process::exit(1);
+                    exit_forkcli(1, Some(child_pid));
                 }
 
                 let mut status: libc::c_int = 0;
-                if unsafe { libc::waitpid(child_pid, &mut status as *mut libc::c_int, 0) } < 0 {
-                    process::exit(1);
+                let options = if persistent { libc::WUNTRACED } else { 0 };
+                if unsafe { libc::waitpid(child_pid, &mut status as *mut libc::c_int, options) } < 0 {
+                    exit_forkcli(1, Some(child_pid));
+                }
+
+                if unsafe { libc::WIFSTOPPED(status) } {
+                    stopped_child = Some(child_pid);
                 }
 
                 let mut status_buf = vec![];
@@ -58,7 +98,7 @@ pub fn start_forkcli() {
                     .write_i32::<LittleEndian>(status)
                     .expect("Could not write to child.");
                 if socket.write(&status_buf).is_err() {
-                    process::exit(1);
+                    exit_forkcli(1, stopped_child);
                 }
             }
         },
@@ -67,4 +107,3 @@ pub fn start_forkcli() {
         },
     }
 }
-
@@ -0,0 +1,78 @@
+/*
+  main() for libFuzzer style harnesses that only define LLVMFuzzerTestOneInput.
+
+  It is a separate member of libruntime_fast.a, so the linker only pulls it in
+  if the program does not define main itself.
+  The input comes from shared memory (--shm_input), the file given as the
+  first argument (@@), or stdin.
+  The signature below makes the fuzzer run the harness in persistent mode.
+ */
+
+#include <fcntl.h>
+#include <stdint.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <unistd.h>
+
+// config::INPUT_SHM_SIZE
+#define MAX_INPUT_SIZE (1 << 20)
+#define PERSIST_MAX_CNT 1000
+// defs.h
+#define PERSIST_SIG "##SIG_ANGORA_PERSISTENT##"
+
+extern int LLVMFuzzerTestOneInput(const uint8_t *data, size_t size);
+__attribute__((weak)) int LLVMFuzzerInitialize(int *argc, char ***argv);
+
+extern const uint8_t *__angora_input_buf(size_t *len);
+extern int __angora_persistent_loop(unsigned int max_cnt);
+
+static size_t read_input(const char *path, uint8_t *buf) {
+  int fd = 0;
+  if (path) {
+    fd = open(path, O_RDONLY);
+    if (fd < 0) {
+      perror("Could not open input file");
+      exit(1);
+    }
+  }
+
+  size_t len = 0;
+  ssize_t n;
+  while (len < MAX_INPUT_SIZE &&
+         (n = read(fd, buf + len, MAX_INPUT_SIZE - len)) > 0) {
+    len += n;
+  }
+
+  if (path)
+    close(fd);
+  return len;
+}
+
+int main(int argc, char **argv) {
+  static volatile const char *sig __attribute__((used));
+  sig = PERSIST_SIG;
+
+  if (LLVMFuzzerInitialize)
+    LLVMFuzzerInitialize(&argc, &argv);
+
+  const char *path = argc > 1 ? argv[1] : NULL;
+  uint8_t *buf = malloc(MAX_INPUT_SIZE);
+
+  while (__angora_persistent_loop(PERSIST_MAX_CNT)) {
+    size_t len = 0;
+    const uint8_t *data = __angora_input_buf(&len);
+    if (!data) {
+      len = read_input(path, buf);
+      data = buf;
+    }
+    // Give the harness a buffer of the exact size, so ASAN catches overflows.
+    uint8_t *copy = malloc(len);
+    memcpy(copy, data, len);
+    LLVMFuzzerTestOneInput(copy, len);
+    free(copy);
+  }
+
+  free(buf);
+  return 0;
+}
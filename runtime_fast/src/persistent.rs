@@ -0,0 +1,42 @@
+// Persistent mode, like AFL's __AFL_LOOP.
+// Usage in the harness:
+//   while (__ANGORA_LOOP(1000)) { read input; process it; }
+// Under the fork server with ANGORA_PERSISTENT set, the child stops itself
+// after each input and the fork server continues it for the next one, instead of forking again.
+// Otherwise the loop body runs exactly once.
+
+use super::{forkcli, shm_branches, shm_conds};
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+
+static FIRST_PASS: AtomicBool = AtomicBool::new(true);
+static CYCLE_CNT: AtomicU32 = AtomicU32::new(0);
+
+#[no_mangle]
+pub extern "C" fn __angora_persistent_loop(max_cnt: u32) -> i32 {
+    let persistent = forkcli::PERSISTENT_MODE.load(Ordering::Relaxed);
+
+    if FIRST_PASS.swap(false, Ordering::Relaxed) {
+        // Coverage before the loop is not part of any input.
+        if persistent {
+            shm_branches::clear_branch_map();
+            shm_conds::reset_shm_conds();
+        }
+        CYCLE_CNT.store(max_cnt, Ordering::Relaxed);
+        return 1;
+    }
+
+    if persistent {
+        let cnt = CYCLE_CNT.load(Ordering::Relaxed).saturating_sub(1);
+        CYCLE_CNT.store(cnt, Ordering::Relaxed);
+        if cnt > 0 {
+            unsafe {
+                libc::raise(libc::SIGSTOP);
+            }
+            // The fuzzer has cleared the branch map before continuing us.
+            shm_conds::reset_shm_conds();
+            return 1;
+        }
+    }
+
+    0
+}
@@ -12,6 +12,14 @@ static mut __ANGORA_AREA_INITIAL: BranchBuf = [255; BRANCHES_SIZE];
 #[no_mangle]
 pub static mut __angora_area_ptr: *const u8 = unsafe{  &__ANGORA_AREA_INITIAL[0] as *const u8 };
 
+pub fn clear_branch_map() {
+    unsafe {
+        if __angora_area_ptr != &__ANGORA_AREA_INITIAL[0] as *const u8 {
+            libc::memset(__angora_area_ptr as *mut libc::c_void, 0, BRANCHES_SIZE);
+        }
+    }
+}
+
 pub fn map_branch_counting_shm() {
     let id_val = env::var(BRANCHES_SHM_ENV_VAR);
     match id_val {
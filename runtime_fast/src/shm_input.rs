@@ -0,0 +1,38 @@
+// map the shared memory of test cases, see fuzzer/src/executor/shm_input.rs
+
+use angora_common::{defs::INPUT_SHM_ENV_VAR, shm, shm_input::ShmInputBuf};
+use std::{env, process, ptr};
+
+static mut INPUT_PTR: *mut ShmInputBuf = ptr::null_mut();
+
+pub fn map_input_shm() {
+    if let Ok(val) = env::var(INPUT_SHM_ENV_VAR) {
+        let shm_id = val.parse::<i32>().expect("Could not parse i32 value.");
+        let mem = shm::SHM::<ShmInputBuf>::from_id(shm_id);
+        if mem.is_fail() {
+            eprintln!("fail to load input shm");
+            process::exit(1);
+        }
+        unsafe {
+            INPUT_PTR = mem.get_ptr();
+        }
+        // The segment is owned by the fuzzer.
+        std::mem::forget(mem);
+    }
+}
+
+// Returns the current input and sets its length,
+// or NULL if the fuzzer does not deliver inputs via shared memory (read the file/stdin instead).
+#[no_mangle]
+pub extern "C" fn __angora_input_buf(len: *mut usize) -> *const u8 {
+    unsafe {
+        if INPUT_PTR.is_null() {
+            return ptr::null();
+        }
+        if !len.is_null() {
+            *len = (*INPUT_PTR).len as usize;
+        }
+        (*INPUT_PTR).fetched = 1;
+        (*INPUT_PTR).buf.as_ptr()
+    }
+}
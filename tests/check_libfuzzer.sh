@@ -0,0 +1,29 @@
+#!/bin/sh
+# Build check for the libFuzzer drivers of the runtimes:
+# a harness without main has to link and run with USE_FAST=1 and USE_TRACK=1.
+set -eux
+
+bin_dir=${BIN_DIR:-../bin/}
+target=libfuzzer/libfuzzer
+input=$(mktemp)
+track_out=$(mktemp)
+trap 'rm -f ${input} ${track_out}' EXIT
+printf 'aaaaaaaaaaaaaaaa' > ${input}
+
+rm -f ${target}.fast ${target}.taint
+USE_FAST=1 ${bin_dir}/angora-clang ${target}.c -o ${target}.fast
+USE_TRACK=1 ${bin_dir}/angora-clang ${target}.c -o ${target}.taint
+
+# The driver in libDFSanIO is not instrumented, so the entry points should keep their names.
+nm ${target}.taint | grep -q ' T LLVMFuzzerTestOneInput$'
+nm ${target}.taint | grep -q ' T LLVMFuzzerInitialize$'
+
+./${target}.fast ${input}
+./${target}.taint ${input}
+./${target}.taint < ${input}
+
+# io_func.o has to be linked for the track output, the harness does not read any file itself.
+rm -f ${track_out}
+ANGORA_TRACK_OUTPUT=${track_out} ./${target}.taint ${input}
+test -s ${track_out}
+echo "libFuzzer drivers OK"
@@ -0,0 +1,33 @@
+/*
+  Test:
+  A libFuzzer style harness without main, which is provided by the runtime.
+  Also a build check: see check_libfuzzer.sh.
+*/
+#include "stdint.h"
+#include "stdio.h"
+#include "stdlib.h"
+#include "string.h"
+
+static int initialized = 0;
+
+int LLVMFuzzerInitialize(int *argc, char ***argv) {
+  initialized = 1;
+  return 0;
+}
+
+int LLVMFuzzerTestOneInput(const uint8_t *data, size_t size) {
+  if (!initialized) {
+    printf("LLVMFuzzerInitialize was not called\n");
+    abort();
+  }
+  if (size < 8)
+    return 0;
+
+  uint32_t x = 0;
+  memcpy(&x, data + 4, 4);
+  if (x == 0x12345678) {
+    printf("hey, you hit it \n");
+    abort();
+  }
+  return 0;
+}